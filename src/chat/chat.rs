@@ -1,37 +1,320 @@
-use anyhow::{Result, anyhow};
+use std::{
+    sync::OnceLock,
+    time::Instant,
+};
+use futures::StreamExt;
+use crate::{UnilangError, Result, RateLimitPermit};
 use super::{
     ChatModel, 
-    ChatModelName, 
     ChatMessage,
+    ChatRequestOptions,
     ChatResponse,
+    ChatResponseStream,
 };
-use super::openai;
 
 impl ChatModel {
     pub async fn get_complete_chat_response(&self, messages: Vec<ChatMessage>) -> Result<ChatResponse> {
-        match self.name {
-            ChatModelName::OpenAIGPT3_5Turbo 
-            | ChatModelName::OpenAIGPT3_5Turbo16K 
-            | ChatModelName::OpenAIGPT4 => {
-                openai::get_complete_chat_response(self, messages).await
-            },
-            _ => {
-                Err(anyhow!("{:?} is not supported", self.name))
-            }
+        self.get_complete_chat_response_with_options(messages, &ChatRequestOptions::default()).await
+    }
+
+    /// Get a complete chat response with options that override the parameters of the model.
+    pub async fn get_complete_chat_response_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponse> {
+        let estimated_tokens = self.estimate_rate_limit_tokens(&messages).await?;
+
+        // Every attempt waits for the rate limiter, so that retries are limited too
+        // The latency is measured from the first attempt, after the wait for the rate limiter
+        let (messages, started_at) = (&messages, &OnceLock::new());
+        let (mut response, permit) = self.retry_policy.retry(|| async move {
+            let permit = self.acquire_rate_limit(estimated_tokens).await;
+            started_at.get_or_init(Instant::now);
+            let response = self.provider.get_complete_chat_response(self, messages.clone(), options).await?;
+
+            Ok((response, permit))
+        }).await?;
+        response.metadata.latency = started_at.get().map(Instant::elapsed);
+
+        // Correct the estimated tokens with the real usage
+        if let Some(permit) = permit {
+            permit.record_usage(response.usage.total_tokens);
+        }
+
+        Ok(response)
+    }
+
+    pub async fn get_streamed_chat_response(&self, messages: Vec<ChatMessage>) -> Result<ChatResponseStream> {
+        self.get_streamed_chat_response_with_options(messages, &ChatRequestOptions::default()).await
+    }
+
+    /// Get a streamed chat response with options that override the parameters of the model.
+    pub async fn get_streamed_chat_response_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponseStream> {
+        let estimated_tokens = self.estimate_rate_limit_tokens(&messages).await?;
+
+        // Every attempt waits for the rate limiter, so that retries are limited too
+        // Only the request is retried, since the stream cannot be resumed
+        let (messages, started_at) = (&messages, &OnceLock::new());
+        let (stream, permit) = self.retry_policy.retry(|| async move {
+            let permit = self.acquire_rate_limit(estimated_tokens).await;
+            started_at.get_or_init(Instant::now);
+            let stream = self.provider.get_streamed_chat_response(self, messages.clone(), options).await?;
+
+            Ok((stream, permit))
+        }).await?;
+
+        // Correct the estimated tokens with the usage, if the provider reports it in the stream
+        let stream = match permit {
+            Some(permit) => ChatResponseStream::new(stream.inspect(move |delta| {
+                if let Some(usage) = delta.as_ref().ok().and_then(|delta| delta.usage.as_ref()) {
+                    permit.record_usage(usage.total_tokens);
+                }
+            })),
+            None => stream,
+        };
+
+        Ok(match started_at.get() {
+            Some(started_at) => stream.with_started_at(*started_at),
+            None => stream,
+        })
+    }
+
+    /// Estimate the tokens of a request for the rate limiter, i.e. the tokens of the prompt.
+    /// The tokens are only counted if the model has a rate limiter.
+    async fn estimate_rate_limit_tokens(&self, messages: &[ChatMessage]) -> Result<u32> {
+        match &self.rate_limiter {
+            Some(_) => self.count_prompt_tokens(messages).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Wait for the rate limiter to let an attempt of the request through.
+    async fn acquire_rate_limit(&self, estimated_tokens: u32) -> Option<RateLimitPermit> {
+        match &self.rate_limiter {
+            Some(rate_limiter) => Some(rate_limiter.acquire(estimated_tokens).await),
+            None => None,
         }
     }
+
+    /// Count the tokens of the prompt made of the profile and the messages.
+    pub async fn count_prompt_tokens(&self, messages: &[ChatMessage]) -> Result<u32> {
+        self.token_counter.count_prompt_tokens(self, messages).await
+    }
+
+    /// Check that the prompt fits in the model before it is sent,
+    /// and return the number of its tokens.
+    pub async fn check_prompt_tokens(&self, messages: &[ChatMessage]) -> Result<u32> {
+        let prompt_tokens = self.count_prompt_tokens(messages).await?;
+
+        if prompt_tokens > self.name.max_prompt_tokens() {
+            return Err(UnilangError::InvalidInput(format!(
+                "the prompt has {} tokens, which exceeds the limit of {} tokens of {:?}",
+                prompt_tokens,
+                self.name.max_prompt_tokens(),
+                self.name,
+            )));
+        }
+
+        Ok(prompt_tokens)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+    use std::sync::atomic::{AtomicU32, Ordering};
     use anyhow::Result;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use tokio::time::Instant;
+    use crate::{UnilangError, RateLimiter, RetryPolicy};
     use crate::chat::{
         ChatModel,
         ChatModelName,
         ChatMessage,
+        ChatProvider,
+        ChatRequestOptions,
+        ChatResponse,
+        ChatResponseMetadata,
         ChatRole,
+        ChatTokenUsage,
     };
 
+    /// A provider that echoes the last message back.
+    #[derive(Debug)]
+    struct EchoChatProvider;
+
+    #[async_trait]
+    impl ChatProvider for EchoChatProvider {
+        async fn get_complete_chat_response(
+            &self,
+            _model: &ChatModel,
+            messages: Vec<ChatMessage>,
+            _options: &ChatRequestOptions,
+        ) -> crate::Result<ChatResponse> {
+            Ok(ChatResponse {
+                content: messages.last().unwrap().content.to_owned(),
+                is_complete: true,
+                usage: ChatTokenUsage {
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    total_tokens: 0,
+                },
+                tool_calls: vec![],
+                choices: vec![],
+                finish_reason: None,
+                metadata: ChatResponseMetadata::default(),
+            })
+        }
+    }
+
+    /// A provider that is overloaded at the first attempt and echoes the last message back after it.
+    #[derive(Debug, Default)]
+    struct OverloadedChatProvider {
+        attempts: AtomicU32,
+    }
+
+    #[async_trait]
+    impl ChatProvider for OverloadedChatProvider {
+        async fn get_complete_chat_response(
+            &self,
+            model: &ChatModel,
+            messages: Vec<ChatMessage>,
+            options: &ChatRequestOptions,
+        ) -> crate::Result<ChatResponse> {
+            match self.attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(UnilangError::HttpStatus {
+                    status: 503,
+                    body: "overloaded".to_string(),
+                    retry_after: None,
+                }),
+                _ => EchoChatProvider.get_complete_chat_response(model, messages, options).await,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_provider() -> Result<()> {
+        let model = ChatModel::builder()
+            .provider(EchoChatProvider)
+            .build();
+
+        let response = model.get_complete_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "Hello, world!"),
+        ]).await?;
+
+        assert_eq!(response.content, "Hello, world!");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_default_streamed_chat_response() -> Result<()> {
+        let model = ChatModel::builder()
+            .provider(EchoChatProvider)
+            .build();
+
+        let stream = model.get_streamed_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "Hello, world!"),
+        ]).await?;
+
+        // The latency of the deltas can be measured from the start of the stream
+        assert!(stream.started_at().is_some_and(|started_at| started_at <= std::time::Instant::now()));
+
+        let deltas = stream
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].content, "Hello, world!");
+        assert!(deltas[0].is_end);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter() -> Result<()> {
+        let rate_limiter = RateLimiter::builder()
+            .requests_per_second(1)
+            .build()?;
+        let model = ChatModel::builder()
+            .provider(EchoChatProvider)
+            .rate_limiter(rate_limiter.clone())
+            .build();
+        let start = Instant::now();
+
+        model.get_complete_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "Hello, world!"),
+        ]).await?;
+        model.get_streamed_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "Hello, world!"),
+        ]).await?
+        .collect::<Vec<_>>()
+        .await;
+
+        // Both paths share the limit
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_retries() -> Result<()> {
+        let model = ChatModel::builder()
+            .provider(OverloadedChatProvider::default())
+            .retry_policy(
+                RetryPolicy::builder()
+                    .initial_backoff(Duration::from_millis(1))
+                    .build()?
+            )
+            .rate_limiter(
+                RateLimiter::builder()
+                    .requests_per_second(1)
+                    .build()?
+            )
+            .build();
+        let start = Instant::now();
+
+        let response = model.get_complete_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "Hello, world!"),
+        ]).await?;
+
+        // The retry waits for the rate limiter as well as the backoff
+        assert_eq!(response.content, "Hello, world!");
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_prompt_tokens() -> Result<()> {
+        let model = ChatModel::builder()
+            .name(ChatModelName::OpenAIGPT4)
+            .build();
+
+        let messages = vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ];
+        assert_eq!(model.check_prompt_tokens(&messages).await?, 11);
+
+        let messages = vec![
+            ChatMessage::new(ChatRole::User, "Rust ".repeat(10000)),
+        ];
+        assert!(matches!(
+            model.check_prompt_tokens(&messages).await,
+            Err(UnilangError::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_get_complete_chat_response() -> Result<()> {
         // Initialize logger
@@ -43,10 +326,7 @@ mod tests {
             .build();
 
         let messages = vec![
-            ChatMessage {
-                role: ChatRole::User,
-                content: "How to spwan a thread? (Explain in one sentence.)".to_string(),
-            },
+            ChatMessage::new(ChatRole::User, "How to spwan a thread? (Explain in one sentence.)"),
         ];
 
         let response = model.get_complete_chat_response(messages).await?;
@@ -1,10 +1,59 @@
 use serde::Serialize;
-use super::ChatRole;
+use serde_with::skip_serializing_none;
+use super::{
+    ChatRole,
+    ChatToolCall,
+};
 
-#[derive(Debug, Serialize)]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize)]
 pub struct ChatMessage {
     pub role: ChatRole,
     pub content: String,
+
+    /// Tools called by the assistant.
+    pub tool_calls: Option<Vec<ChatToolCall>>,
+
+    /// ID of the tool call that a tool message responds to.
+    pub tool_call_id: Option<String>,
+
+    /// Name of the tool that a tool message responds to.
+    pub tool_name: Option<String>,
+}
+
+impl ChatMessage {
+    /// Create a message with text content.
+    pub fn new<S: AsRef<str>>(role: ChatRole, content: S) -> Self {
+        Self {
+            role,
+            content: content.as_ref().to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
+        }
+    }
+
+    /// Create an assistant message that calls tools.
+    pub fn tool_calls(tool_calls: Vec<ChatToolCall>) -> Self {
+        Self {
+            role: ChatRole::Assistant,
+            content: String::new(),
+            tool_calls: Some(tool_calls),
+            tool_call_id: None,
+            tool_name: None,
+        }
+    }
+
+    /// Create a tool message with the result of a tool call.
+    pub fn tool_result<S: AsRef<str>>(tool_call: &ChatToolCall, content: S) -> Self {
+        Self {
+            role: ChatRole::Tool,
+            content: content.as_ref().to_string(),
+            tool_calls: None,
+            tool_call_id: Some(tool_call.id.to_owned()),
+            tool_name: Some(tool_call.name.to_owned()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -17,9 +66,12 @@ mod tests {
         let default = ChatMessage {
             role: ChatRole::User,
             content: "Hello, world!".to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            tool_name: None,
         };
         let expected = r#"{"role":"user","content":"Hello, world!"}"#;
 
         assert_eq!(serde_json::to_string(&default).unwrap(), expected);
     }
-}
\ No newline at end of file
+}
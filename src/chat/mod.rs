@@ -1,7 +1,7 @@
 mod chat;
 
 mod model;
-pub use model::ChatModel;
+pub use model::{ChatModel, ChatModelBuilder};
 
 mod model_names;
 pub use model_names::ChatModelName;
@@ -12,8 +12,32 @@ pub use role::ChatRole;
 mod message;
 pub use message::ChatMessage;
 
+mod tool;
+pub use tool::{ChatTool, ChatToolCall, ChatToolCallDelta};
+
 mod response;
-pub use response::{ChatResponse, ChatTokenUsage};
+pub use response::{
+    ChatResponse,
+    ChatChoice,
+    ChatResponseMetadata,
+    FinishReason,
+    ChatTokenUsage,
+    ChatResponseDelta,
+    ChatResponseStream,
+    ChatResponseAggregator,
+};
+
+mod options;
+pub use options::{ChatRequestOptions, ChatRequestOptionsBuilder};
+
+mod provider;
+pub use provider::ChatProvider;
+
+mod session;
+pub use session::{ChatSession, ChatSessionBuilder, ChatTrimStrategy};
 
 mod openai;
+pub use openai::OpenAIChatProvider;
+
 mod qianfan;
+pub use qianfan::QianfanChatProvider;
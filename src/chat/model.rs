@@ -1,33 +1,83 @@
-use std::time::Duration;
+use std::{
+    sync::Arc,
+    time::Duration,
+};
 use reqwest::Client;
-use super::ChatModelName;
+use crate::{
+    CredentialProvider,
+    EnvCredentialProvider,
+    RateLimiter,
+    RetryPolicy,
+    tokenizer::{
+        TokenCounter,
+        OpenAITokenCounter,
+        QianfanTokenEstimator,
+    },
+};
+use super::{
+    ChatModelName,
+    ChatProvider,
+    ChatTool,
+    OpenAIChatProvider,
+    QianfanChatProvider,
+};
 
 #[derive(Debug)]
 pub struct ChatModel {
     pub client: Client,
+
+    /// Base URL of the provider's API, which is only set to call it through a proxy.
+    pub api_base: Option<String>,
+
     pub name: ChatModelName,
-    pub temperature: f32,
-    pub top_p: f32,
-    pub presence_penalty: f32,
+    /// Sampling parameters, which are left to the defaults of the provider if they are not set.
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub presence_penalty: Option<f32>,
+
     pub profile: Option<String>,
+    pub tools: Vec<ChatTool>,
+    pub provider: Box<dyn ChatProvider>,
+    pub credential_provider: Box<dyn CredentialProvider>,
+    pub token_counter: Box<dyn TokenCounter>,
+    /// Policy to retry the requests that fail temporarily, whatever the provider.
+    pub retry_policy: RetryPolicy,
+
+    /// Limiter shared with the other models of the same provider account.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl ChatModel {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         client: Client,
+        api_base: Option<String>,
         name: ChatModelName,
-        temperature: f32,
-        top_p: f32,
-        presence_penalty: f32,
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+        presence_penalty: Option<f32>,
         profile: Option<String>,
+        tools: Vec<ChatTool>,
+        provider: Box<dyn ChatProvider>,
+        credential_provider: Box<dyn CredentialProvider>,
+        token_counter: Box<dyn TokenCounter>,
+        retry_policy: RetryPolicy,
+        rate_limiter: Option<Arc<RateLimiter>>,
     ) -> Self {
         Self {
             client,
+            api_base,
             name,
             temperature,
             top_p,
             presence_penalty,
             profile,
+            tools,
+            provider,
+            credential_provider,
+            token_counter,
+            retry_policy,
+            rate_limiter,
         }
     }
 
@@ -39,11 +89,24 @@ impl ChatModel {
 
 pub struct ChatModelBuilder {
     client: Client,
+    api_base: Option<String>,
     name: ChatModelName,
-    temperature: f32,
-    top_p: f32,
-    presence_penalty: f32,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    presence_penalty: Option<f32>,
     profile: Option<String>,
+    tools: Vec<ChatTool>,
+    provider: Option<Box<dyn ChatProvider>>,
+    credential_provider: Box<dyn CredentialProvider>,
+    token_counter: Option<Box<dyn TokenCounter>>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Default for ChatModelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ChatModelBuilder {
@@ -53,11 +116,18 @@ impl ChatModelBuilder {
                 .timeout(Duration::from_secs(60))
                 .build()
                 .unwrap(),
+            api_base: None,
             name: ChatModelName::OpenAIGPT3_5Turbo16K,
-            temperature: 1.0,
-            top_p: 1.0,
-            presence_penalty: 1.0,
+            temperature: None,
+            top_p: None,
+            presence_penalty: None,
             profile: None,
+            tools: vec![],
+            provider: None,
+            credential_provider: Box::new(EnvCredentialProvider),
+            token_counter: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
         }
     }
 
@@ -66,6 +136,13 @@ impl ChatModelBuilder {
         self
     }
 
+    /// Set the base URL of the provider's API, e.g. to call it through a proxy.
+    /// The official API of the provider is called by default.
+    pub fn api_base<S: AsRef<str>>(mut self, api_base: S) -> Self {
+        self.api_base = Some(api_base.as_ref().trim_end_matches('/').to_string());
+        self
+    }
+
     /// Set the name of the chat model.
     pub fn name(mut self, name: ChatModelName) -> Self {
         self.name = name;
@@ -74,18 +151,18 @@ impl ChatModelBuilder {
 
     /// Set the temperature of the chat model.
     pub fn temperature(mut self, temperature: f32) -> Self {
-        self.temperature = temperature;
+        self.temperature = Some(temperature);
         self
     }
 
     pub fn top_p(mut self, top_p: f32) -> Self {
-        self.top_p = top_p;
+        self.top_p = Some(top_p);
         self
     }
 
     /// Set the presence penalty of the chat model.
     pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
-        self.presence_penalty = presence_penalty;
+        self.presence_penalty = Some(presence_penalty);
         self
     }
 
@@ -95,15 +172,113 @@ impl ChatModelBuilder {
         self
     }
 
+    /// Set the tools that the model may call.
+    pub fn tools(mut self, tools: Vec<ChatTool>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Add a tool that the model may call.
+    pub fn tool(mut self, tool: ChatTool) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    /// Set the provider that serves the chat requests.
+    /// If it is not set, the provider is chosen according to the model name.
+    pub fn provider<P: ChatProvider + 'static>(mut self, provider: P) -> Self {
+        self.provider = Some(Box::new(provider));
+        self
+    }
+
+    /// Set the source of the API keys.
+    /// The keys are read from environment variables by default.
+    pub fn credential_provider<P: CredentialProvider + 'static>(mut self, credential_provider: P) -> Self {
+        self.credential_provider = Box::new(credential_provider);
+        self
+    }
+
+    /// Set the counter of prompt tokens.
+    /// If it is not set, the counter is chosen according to the model name.
+    pub fn token_counter<C: TokenCounter + 'static>(mut self, token_counter: C) -> Self {
+        self.token_counter = Some(Box::new(token_counter));
+        self
+    }
+
+    /// Set how failed requests are sent again.
+    /// Temporary errors are retried up to 3 times in total by default.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set the limiter of requests and tokens,
+    /// which should be shared by all the models of the same provider account.
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
     /// Build the chat model.
     pub fn build(self) -> ChatModel {
+        // Choose the built-in provider if no provider is set
+        let provider = match self.provider {
+            Some(provider) => provider,
+            None => default_provider(&self.name),
+        };
+
+        // Qianfan's models are estimated offline by default to avoid extra requests
+        let token_counter = match self.token_counter {
+            Some(token_counter) => token_counter,
+            None => default_token_counter(&self.name),
+        };
+
         ChatModel::new(
             self.client,
+            self.api_base,
             self.name,
             self.temperature,
             self.top_p,
             self.presence_penalty,
             self.profile,
+            self.tools,
+            provider,
+            self.credential_provider,
+            token_counter,
+            self.retry_policy,
+            self.rate_limiter,
         )
     }
-}
\ No newline at end of file
+}
+
+/// Get the built-in provider of the given model.
+fn default_provider(model_name: &ChatModelName) -> Box<dyn ChatProvider> {
+    match model_name {
+        ChatModelName::OpenAIGPT3_5Turbo
+        | ChatModelName::OpenAIGPT3_5Turbo16K
+        | ChatModelName::OpenAIGPT4 => Box::new(OpenAIChatProvider),
+        ChatModelName::QianfanErnieBot4
+        | ChatModelName::QianfanErnieBot
+        | ChatModelName::QianfanErnieBotTurbo
+        | ChatModelName::QianfanLlama2Of7BChat
+        | ChatModelName::QianfanLlama2Of13BChat
+        | ChatModelName::QianfanLlama2Of70BChat
+        | ChatModelName::QianfanChineseLlama2Of7B => Box::new(QianfanChatProvider),
+    }
+}
+
+/// Get the built-in token counter of the given model.
+fn default_token_counter(model_name: &ChatModelName) -> Box<dyn TokenCounter> {
+    match model_name {
+        ChatModelName::OpenAIGPT3_5Turbo
+        | ChatModelName::OpenAIGPT3_5Turbo16K
+        | ChatModelName::OpenAIGPT4 => Box::new(OpenAITokenCounter),
+        ChatModelName::QianfanErnieBot4
+        | ChatModelName::QianfanErnieBot
+        | ChatModelName::QianfanErnieBotTurbo
+        | ChatModelName::QianfanLlama2Of7BChat
+        | ChatModelName::QianfanLlama2Of13BChat
+        | ChatModelName::QianfanLlama2Of70BChat
+        | ChatModelName::QianfanChineseLlama2Of7B => Box::new(QianfanTokenEstimator),
+    }
+}
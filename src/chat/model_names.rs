@@ -3,5 +3,28 @@ pub enum ChatModelName {
     OpenAIGPT3_5Turbo,
     OpenAIGPT3_5Turbo16K,
     OpenAIGPT4,
+    QianfanErnieBot4,
+    QianfanErnieBot,
     QianfanErnieBotTurbo,
-}
\ No newline at end of file
+    QianfanLlama2Of7BChat,
+    QianfanLlama2Of13BChat,
+    QianfanLlama2Of70BChat,
+    QianfanChineseLlama2Of7B,
+}
+
+impl ChatModelName {
+    /// Maximum number of tokens in the prompt of the model.
+    pub fn max_prompt_tokens(&self) -> u32 {
+        match self {
+            ChatModelName::OpenAIGPT3_5Turbo => 4096,
+            ChatModelName::OpenAIGPT3_5Turbo16K => 16385,
+            ChatModelName::OpenAIGPT4 => 8192,
+            ChatModelName::QianfanErnieBot4 | ChatModelName::QianfanErnieBot => 2000,
+            ChatModelName::QianfanErnieBotTurbo => 7168,
+            ChatModelName::QianfanLlama2Of7BChat
+            | ChatModelName::QianfanLlama2Of13BChat
+            | ChatModelName::QianfanLlama2Of70BChat
+            | ChatModelName::QianfanChineseLlama2Of7B => 4096,
+        }
+    }
+}
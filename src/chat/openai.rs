@@ -1,68 +1,221 @@
-use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
 use crate::{
+    UnilangError,
+    Result,
     chat::{
         ChatModel, 
         ChatModelName, 
         ChatMessage,
+        ChatProvider,
+        ChatRequestOptions,
         ChatRole,
+        ChatChoice,
         ChatResponse,
+        ChatResponseDelta,
+        ChatResponseMetadata,
+        ChatResponseStream,
         ChatTokenUsage,
+        ChatToolCall,
+        ChatToolCallDelta,
+        FinishReason,
+        options::clamp_parameter,
     },
     openai::{
         self,
         chat::{
             OpenAIChatRequestBody, 
             OpenAIChatCompletion, 
+            OpenAIChatCompletionChunk,
             OpenAIChatMessage,
             OpenAIChatRole,
+            OpenAIChatTool,
+            OpenAIFunctionCall,
+            OpenAIFunctionDefinition,
+            OpenAIStreamOptions,
+            OpenAIToolCall,
+            OpenAIToolType,
         }
     },
 };
 
-pub async fn get_complete_chat_response(
+/// The name of OpenAI in the metadata of responses.
+const PROVIDER_NAME: &str = "openai";
+
+/// The provider that serves chat requests with OpenAI's chat API.
+#[derive(Debug, Default)]
+pub struct OpenAIChatProvider;
+
+#[async_trait]
+impl ChatProvider for OpenAIChatProvider {
+    async fn get_complete_chat_response(
+        &self,
+        model: &ChatModel,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponse> {
+        let api_base = model.api_base.as_deref().unwrap_or(openai::OPENAI_API_BASE);
+        let credentials = model.credential_provider.get_credentials().await?;
+        let request_body = create_request_body(model, messages, options)?;
+
+        // Call API to get chat response
+        Ok(
+            openai::chat::get_complete_chat_response(
+                &model.client,
+                api_base,
+                &credentials,
+                &request_body,
+            ).await?
+            .into()
+        )
+    }
+
+    async fn get_streamed_chat_response(
+        &self,
+        model: &ChatModel,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponseStream> {
+        let api_base = model.api_base.as_deref().unwrap_or(openai::OPENAI_API_BASE);
+        let credentials = model.credential_provider.get_credentials().await?;
+        let mut request_body = create_request_body(model, messages, options)?;
+
+        // OpenAI only reports token usage in the stream if it is asked to
+        request_body.stream_options = Some(OpenAIStreamOptions {
+            include_usage: true,
+        });
+
+        // Call API to get the streamed chat response
+        let stream = openai::chat::get_streamed_chat_response(
+            &model.client,
+            api_base,
+            &credentials,
+            &request_body,
+        ).await?;
+
+        Ok(
+            ChatResponseStream::new(stream.flat_map(|chunk| {
+                let deltas = match chunk {
+                    Ok(chunk) => chunk_to_deltas(chunk)
+                        .into_iter()
+                        .map(Ok)
+                        .collect(),
+                    Err(error) => vec![Err(error)],
+                };
+
+                stream::iter(deltas)
+            }))
+        )
+    }
+}
+
+/// Create the request body of OpenAI's chat API.
+fn create_request_body(
     model: &ChatModel,
     messages: Vec<ChatMessage>,
-) -> Result<ChatResponse> {
+    options: &ChatRequestOptions,
+) -> Result<OpenAIChatRequestBody> {
     // Get the model name
     let model_name = chat_model_name_to_string(&model.name)?;
 
-    // Call API to get chat response
-    Ok(
-        openai::chat::get_complete_chat_response(
-            &model.client,
-            &OpenAIChatRequestBody::builder()
-                .model(model_name.as_str())
-                .messages(
-                    // Add profile to the first message if it exists
-                    match &model.profile {
-                        Some(profile) => vec![
-                            OpenAIChatMessage {
-                                role: OpenAIChatRole::System,
-                                content: profile.to_string(),
-                            }
-                        ],
-                        None => vec![],
-                    }.into_iter()
-
-                    // Messages of the user and the assistant
-                    .chain(
-                        messages
+    let mut builder = OpenAIChatRequestBody::builder()
+        .model(model_name.as_str())
+        .messages(
+            // Add profile to the first message if it exists
+            match &model.profile {
+                Some(profile) => vec![
+                    OpenAIChatMessage::new(OpenAIChatRole::System, profile)
+                ],
+                None => vec![],
+            }.into_iter()
+            .map(Ok)
+
+            // Messages of the user, the assistant and the tools
+            .chain(
+                messages
+                .iter()
+                .map(chat_message_to_openai_message)
+            )
+            .collect::<Result<Vec<OpenAIChatMessage>>>()?
+        );
+
+    // Parameters that are set neither for the request nor for the model
+    // are left to the defaults of OpenAI
+    let options = options.or_model(model);
+    if let Some(temperature) = clamp_parameter("temperature", options.temperature, 0.0..=2.0)? {
+        builder = builder.temperature(temperature);
+    }
+    if let Some(top_p) = clamp_parameter("top_p", options.top_p, 0.0..=1.0)? {
+        builder = builder.top_p(top_p);
+    }
+    if let Some(presence_penalty) = clamp_parameter("presence_penalty", options.presence_penalty, -2.0..=2.0)? {
+        builder = builder.presence_penalty(presence_penalty);
+    }
+    match options.n {
+        Some(0) => return Err(UnilangError::InvalidInput(
+            "n must be at least 1".to_string()
+        )),
+        Some(n) => builder = builder.n(n),
+        None => {},
+    }
+
+    // OpenAI rejects an empty list of tools
+    if model.tools.is_empty() {
+        Ok(builder.build())
+    } else {
+        Ok(
+            builder
+                .tools(
+                    model.tools
                         .iter()
-                        .map(|message| OpenAIChatMessage {
-                            role: match message.role {
-                                ChatRole::User => OpenAIChatRole::User,
-                                ChatRole::Assistant => OpenAIChatRole::Assistant,
+                        .map(|tool| OpenAIChatTool::function(OpenAIFunctionDefinition {
+                            name: tool.name.to_owned(),
+                            description: Some(tool.description.to_owned()),
+                            parameters: tool.parameters.to_owned(),
+                        }))
+                        .collect()
+                )
+                .build()
+        )
+    }
+}
+
+/// Convert a `ChatMessage` to a message of OpenAI's chat API.
+///
+/// An error is returned if a tool message does not tell which tool call it responds to.
+fn chat_message_to_openai_message(message: &ChatMessage) -> Result<OpenAIChatMessage> {
+    match message.role {
+        ChatRole::User => Ok(OpenAIChatMessage::new(OpenAIChatRole::User, &message.content)),
+        ChatRole::Assistant => match &message.tool_calls {
+            Some(tool_calls) => Ok(OpenAIChatMessage {
+                // Keep the content if the assistant says something along with the calls
+                content: match message.content.is_empty() {
+                    true => None,
+                    false => Some(message.content.to_owned()),
+                },
+                ..OpenAIChatMessage::tool_calls(
+                    tool_calls
+                        .iter()
+                        .map(|tool_call| OpenAIToolCall {
+                            id: tool_call.id.to_owned(),
+                            tool_type: OpenAIToolType::Function,
+                            function: OpenAIFunctionCall {
+                                name: tool_call.name.to_owned(),
+                                arguments: tool_call.arguments.to_owned(),
                             },
-                            content: message.content.to_owned(),
                         })
-                    )
-                    .collect::<Vec<OpenAIChatMessage>>()
+                        .collect()
                 )
-                .temperature(0.9)
-                .build()
-        ).await?
-        .into()
-    )
+            }),
+            None => Ok(OpenAIChatMessage::new(OpenAIChatRole::Assistant, &message.content)),
+        },
+        ChatRole::Tool => match &message.tool_call_id {
+            Some(tool_call_id) => Ok(OpenAIChatMessage::tool_result(tool_call_id, &message.content)),
+            None => Err(UnilangError::InvalidInput(
+                "a tool message must have a tool_call_id".to_string()
+            )),
+        },
+    }
 }
 
 /// Convert a `ChatModelName` to a `String` that can be used in OpenAI's API.
@@ -71,57 +224,589 @@ fn chat_model_name_to_string(model_name: &ChatModelName) -> Result<String> {
         ChatModelName::OpenAIGPT3_5Turbo => Ok("gpt-3.5-turbo".to_string()),
         ChatModelName::OpenAIGPT3_5Turbo16K => Ok("gpt-3.5-turbo-16k".to_string()),
         ChatModelName::OpenAIGPT4 => Ok("gpt-4".to_string()),
-        _ => Err(anyhow!("{:?} is not available in OpenAI's chat models", model_name)),
+        _ => Err(UnilangError::UnsupportedModel(
+            format!("{:?} is not available in OpenAI's chat models", model_name)
+        )),
     }
 }
 
 impl From<OpenAIChatCompletion> for ChatResponse {
     fn from(response: OpenAIChatCompletion) -> Self {
+        let mut choices = response.choices
+            .into_iter()
+            .map(|choice| ChatChoice {
+                index: choice.index,
+                content: choice.message
+                    .content
+                    .unwrap_or_default(),
+                finish_reason: Some(choice.finish_reason.into()),
+                tool_calls: choice.message
+                    .tool_calls
+                    .into_iter()
+                    .flatten()
+                    .map(|tool_call| ChatToolCall {
+                        id: tool_call.id,
+                        name: tool_call.function.name,
+                        arguments: tool_call.function.arguments,
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        choices.sort_by_key(|choice| choice.index);
+
+        // OpenAI may return no choice at all, e.g. if the content is filtered
+        let first_choice = choices.first();
+
         Self {
-            content: response.choices
-                .first()
-                .unwrap()
-                .message
-                .content
-                .to_owned(),
-            is_complete: true,
+            content: first_choice
+                .map(|choice| choice.content.to_owned())
+                .unwrap_or_default(),
+            is_complete: !choices.is_empty() && choices.iter().all(|choice| {
+                !matches!(&choice.finish_reason, Some(reason) if reason.is_truncated())
+            }),
+            finish_reason: first_choice.and_then(|choice| choice.finish_reason.to_owned()),
             usage: ChatTokenUsage { 
                 prompt_tokens: response.usage.prompt_tokens, 
                 completion_tokens: response.usage.completion_tokens, 
                 total_tokens: response.usage.total_tokens,
-            }
+            },
+            tool_calls: first_choice
+                .map(|choice| choice.tool_calls.to_owned())
+                .unwrap_or_default(),
+            choices,
+            metadata: ChatResponseMetadata {
+                id: response.id,
+                provider: PROVIDER_NAME.to_string(),
+                model: response.model,
+                created: response.created,
+                latency: None,
+            },
         }
     }
 }
 
+/// Convert a chunk to a delta for each choice in it.
+fn chunk_to_deltas(chunk: OpenAIChatCompletionChunk) -> Vec<ChatResponseDelta> {
+    // OpenAI only reports token usage in an extra last chunk without choices
+    // if it is requested with `stream_options`
+    let usage = chunk.usage.map(|usage| ChatTokenUsage {
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        total_tokens: usage.total_tokens,
+    });
+    let metadata = ChatResponseMetadata {
+        id: chunk.id,
+        provider: PROVIDER_NAME.to_string(),
+        model: chunk.model,
+        created: chunk.created,
+        latency: None,
+    };
+
+    if chunk.choices.is_empty() {
+        return usage
+            .map(|usage| ChatResponseDelta {
+                content: String::new(),
+                is_end: false,
+                index: 0,
+                finish_reason: None,
+                tool_calls: vec![],
+                usage: Some(usage),
+                metadata: Some(metadata),
+            })
+            .into_iter()
+            .collect();
+    }
+
+    chunk.choices
+        .into_iter()
+        .map(|choice| ChatResponseDelta {
+            content: choice.delta.content.unwrap_or_default(),
+            is_end: choice.finish_reason.is_some(),
+            index: choice.index,
+            finish_reason: choice.finish_reason.map(FinishReason::from),
+            tool_calls: choice.delta.tool_calls
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tool_call| ChatToolCallDelta {
+                    index: tool_call.index,
+                    id: tool_call.id,
+                    name: tool_call.function
+                        .as_ref()
+                        .and_then(|function| function.name.to_owned()),
+                    arguments: tool_call.function
+                        .and_then(|function| function.arguments)
+                        .unwrap_or_default(),
+                })
+                .collect(),
+            usage: usage.clone(),
+            metadata: Some(metadata.clone()),
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, Instant};
     use anyhow::Result;
-    use crate::chat::{
-        ChatModel,
-        ChatModelName,
-        ChatMessage,
-        ChatRole,
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        RetryPolicy,
+        StaticCredentialProvider,
+        UnilangError,
+        chat::{
+            ChatModel,
+            ChatModelName,
+            ChatMessage,
+            ChatProvider,
+            ChatRequestOptions,
+            ChatResponse,
+            ChatRole,
+            ChatTool,
+            ChatToolCall,
+            ChatToolCallDelta,
+            FinishReason,
+        },
+        openai::chat::{
+            OpenAIChatCompletion,
+            OpenAIChatCompletionChunk,
+        },
+    };
+    use super::{
+        chunk_to_deltas,
+        create_request_body,
+        OpenAIChatProvider,
     };
-    use super::get_complete_chat_response;
 
     #[tokio::test]
     async fn test_get_complete_chat_response() -> Result<()> {
-        let response = get_complete_chat_response(
+        let response = OpenAIChatProvider.get_complete_chat_response(
             &ChatModel::builder()
                 .name(ChatModelName::OpenAIGPT3_5Turbo16K)
                 .temperature(0.1)
                 .build(),
             vec![
-                ChatMessage {
-                    role: ChatRole::User,
-                    content: "What is Rust?".to_string(),
-                },
+                ChatMessage::new(ChatRole::User, "What is Rust?"),
             ],
+            &ChatRequestOptions::default(),
         ).await?;
 
         println!("{:#?}", response);
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_retry_after_rate_limit() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("Retry-After", "1")
+                    .set_body_raw(
+                        r#"{"error":{"message":"Rate limit reached for requests","type":"requests","param":null,"code":"rate_limit_exceeded"}}"#,
+                        "application/json",
+                    )
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"id":"chatcmpl-1","object":"chat.completion","created":1700000000,"model":"gpt-4","choices":[{"index":0,"message":{"role":"assistant","content":"Rust is a language."},"finish_reason":"stop"}],"usage":{"prompt_tokens":11,"completion_tokens":5,"total_tokens":16}}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let model = ChatModel::builder()
+            .name(ChatModelName::OpenAIGPT4)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .openai_api_key("sk-test")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .retry_policy(
+                RetryPolicy::builder()
+                    .initial_backoff(Duration::from_millis(1))
+                    .build()?
+            )
+            .build();
+
+        let start = Instant::now();
+        let response = model.get_complete_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ]).await?;
+
+        // The delay asked by the server is longer than the backoff
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(response.content, "Rust is a language.");
+
+        // The latency covers the retries
+        assert!(response.metadata.latency.is_some_and(|latency| latency >= Duration::from_secs(1)));
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_streamed_chat_response() -> Result<()> {
+        let chunk = |delta: &str, finish_reason: &str| format!(
+            "data: {{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4\",\"choices\":[{{\"index\":0,\"delta\":{},\"finish_reason\":{}}}]}}\n\n",
+            delta,
+            finish_reason,
+        );
+        let usage = |completion_tokens: u32| format!(
+            "data: {{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4\",\"choices\":[],\"usage\":{{\"prompt_tokens\":11,\"completion_tokens\":{},\"total_tokens\":{}}}}}\n\n",
+            completion_tokens,
+            11 + completion_tokens,
+        );
+        let server = MockServer::start().await;
+        Mock::given(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                [
+                    chunk(r#"{"role":"assistant","content":"Rust is"}"#, "null"),
+                    chunk(r#"{"content":" a language."}"#, "null"),
+                    chunk("{}", r#""stop""#),
+                    usage(6),
+                    "data: [DONE]\n\n".to_string(),
+                ].concat(),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        let model = ChatModel::builder()
+            .name(ChatModelName::OpenAIGPT4)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .openai_api_key("sk-test")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .build();
+
+        let mut deltas = vec![];
+        let response = model.get_streamed_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ]).await?
+        .collect_response_with(|delta| deltas.push(delta.content.to_owned()))
+        .await?;
+
+        assert_eq!(deltas, vec!["Rust is", " a language.", "", ""]);
+        assert_eq!(response.content, "Rust is a language.");
+        assert!(response.is_complete);
+        assert_eq!(response.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(response.metadata.id, "chatcmpl-1");
+        assert!(response.metadata.latency.is_some());
+
+        // The usage is reported in the last chunk once it is asked for
+        assert_eq!(
+            server.received_requests().await.unwrap()[0].body_json::<serde_json::Value>()?["stream_options"],
+            json!({"include_usage": true})
+        );
+        assert_eq!(response.usage.prompt_tokens, 11);
+        assert_eq!(response.usage.completion_tokens, 6);
+        assert_eq!(response.usage.total_tokens, 17);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collect_streamed_choices() -> Result<()> {
+        let chunk = |index: u32, delta: &str, finish_reason: &str| format!(
+            "data: {{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4\",\"choices\":[{{\"index\":{},\"delta\":{},\"finish_reason\":{}}}]}}\n\n",
+            index,
+            delta,
+            finish_reason,
+        );
+        let usage = |completion_tokens: u32| format!(
+            "data: {{\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4\",\"choices\":[],\"usage\":{{\"prompt_tokens\":11,\"completion_tokens\":{},\"total_tokens\":{}}}}}\n\n",
+            completion_tokens,
+            11 + completion_tokens,
+        );
+        let server = MockServer::start().await;
+        Mock::given(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                [
+                    chunk(0, r#"{"role":"assistant","content":"Rust is"}"#, "null"),
+                    chunk(1, r#"{"role":"assistant","content":"Rust"}"#, "null"),
+                    chunk(1, r#"{"content":" is fast."}"#, "null"),
+                    chunk(0, r#"{"content":" a language."}"#, "null"),
+                    chunk(1, "{}", r#""stop""#),
+                    chunk(0, "{}", r#""stop""#),
+                    usage(11),
+                    "data: [DONE]\n\n".to_string(),
+                ].concat(),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        let model = ChatModel::builder()
+            .name(ChatModelName::OpenAIGPT4)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .openai_api_key("sk-test")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .build();
+
+        let response = model.get_streamed_chat_response_with_options(
+            vec![
+                ChatMessage::new(ChatRole::User, "What is Rust?"),
+            ],
+            &ChatRequestOptions::builder()
+                .n(2)
+                .build(),
+        ).await?
+        .collect_response()
+        .await?;
+
+        assert_eq!(server.received_requests().await.unwrap()[0].body_json::<serde_json::Value>()?["n"], json!(2));
+        assert!(response.is_complete);
+        assert_eq!(response.content, "Rust is a language.");
+        assert_eq!(
+            response.choices
+                .iter()
+                .map(|choice| choice.content.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Rust is a language.", "Rust is fast."]
+        );
+
+        // The usage covers both choices
+        assert_eq!(response.usage.completion_tokens, 11);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_body_with_parameters() -> Result<()> {
+        let model = ChatModel::builder()
+            .name(ChatModelName::OpenAIGPT4)
+            .temperature(0.5)
+            .top_p(0.75)
+            .build();
+        let messages = vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ];
+
+        // Parameters of the model
+        let request_body = serde_json::to_value(create_request_body(&model, messages.clone(), &ChatRequestOptions::default())?)?;
+        assert_eq!(request_body["temperature"], json!(0.5));
+        assert_eq!(request_body["top_p"], json!(0.75));
+        assert!(request_body.get("presence_penalty").is_none());
+
+        // Overridden by the request and clamped
+        let options = ChatRequestOptions::builder()
+            .temperature(3.0)
+            .presence_penalty(-0.5)
+            .build();
+        let request_body = serde_json::to_value(create_request_body(&model, messages.clone(), &options)?)?;
+        assert_eq!(request_body["temperature"], json!(2.0));
+        assert_eq!(request_body["top_p"], json!(0.75));
+        assert_eq!(request_body["presence_penalty"], json!(-0.5));
+
+        // Not a number
+        let options = ChatRequestOptions::builder()
+            .top_p(f32::NAN)
+            .build();
+        assert!(matches!(
+            create_request_body(&model, messages, &options),
+            Err(UnilangError::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_body_with_tools() -> Result<()> {
+        let model = ChatModel::builder()
+            .name(ChatModelName::OpenAIGPT4)
+            .tool(ChatTool::new(
+                "get_weather",
+                "Get the current weather of a city",
+                json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+            ))
+            .build();
+        let tool_call = ChatToolCall {
+            id: "call_1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: r#"{"city":"Beijing"}"#.to_string(),
+        };
+
+        let request_body = create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "How is the weather in Beijing?"),
+                ChatMessage::tool_calls(vec![tool_call.clone()]),
+                ChatMessage::tool_result(&tool_call, "Sunny"),
+            ],
+            &ChatRequestOptions::default(),
+        )?;
+        let request_body = serde_json::to_value(request_body)?;
+
+        assert_eq!(
+            request_body["messages"],
+            json!([
+                {"role": "user", "content": "How is the weather in Beijing?"},
+                {
+                    "role": "assistant",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Beijing\"}"},
+                    }],
+                },
+                {"role": "tool", "content": "Sunny", "tool_call_id": "call_1"},
+            ])
+        );
+        assert_eq!(
+            request_body["tools"],
+            json!([{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Get the current weather of a city",
+                    "parameters": {"type": "object", "properties": {"city": {"type": "string"}}},
+                },
+            }])
+        );
+
+        // A tool message must tell which tool call it responds to
+        let result = create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "How is the weather in Beijing?"),
+                ChatMessage::tool_calls(vec![tool_call.clone()]),
+                ChatMessage::new(ChatRole::Tool, "Sunny"),
+            ],
+            &ChatRequestOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(UnilangError::InvalidInput(message)) if message.contains("tool_call_id")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_completion_with_tool_calls() -> Result<()> {
+        let completion: OpenAIChatCompletion = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion","created":1700000000,"model":"gpt-4","choices":[{"index":0,"message":{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"city\":\"Beijing\"}"}}]},"finish_reason":"tool_calls"}],"usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15}}"#
+        )?;
+        let response = ChatResponse::from(completion);
+
+        assert_eq!(response.content, "");
+        assert_eq!(
+            response.tool_calls,
+            vec![ChatToolCall {
+                id: "call_1".to_string(),
+                name: "get_weather".to_string(),
+                arguments: r#"{"city":"Beijing"}"#.to_string(),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_completion_with_choices() -> Result<()> {
+        let completion: OpenAIChatCompletion = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion","created":1700000000,"model":"gpt-4-0613","choices":[{"index":1,"message":{"role":"assistant","content":"Rust is"},"finish_reason":"length"},{"index":0,"message":{"role":"assistant","content":"Rust is a language."},"finish_reason":"stop"}],"usage":{"prompt_tokens":10,"completion_tokens":8,"total_tokens":18}}"#
+        )?;
+        let response = ChatResponse::from(completion);
+
+        assert_eq!(response.content, "Rust is a language.");
+        assert_eq!(response.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(
+            response.choices
+                .iter()
+                .map(|choice| (choice.index, choice.content.as_str(), choice.finish_reason.to_owned()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "Rust is a language.", Some(FinishReason::Stop)),
+                (1, "Rust is", Some(FinishReason::Length)),
+            ]
+        );
+
+        // The second choice is cut off
+        assert!(!response.is_complete);
+
+        assert_eq!(response.metadata.id, "chatcmpl-1");
+        assert_eq!(response.metadata.provider, "openai");
+        assert_eq!(response.metadata.model, "gpt-4-0613");
+        assert_eq!(response.metadata.created, 1700000000);
+        assert_eq!(response.metadata.latency, None);
+
+        // No choice at all
+        let completion: OpenAIChatCompletion = serde_json::from_str(
+            r#"{"id":"chatcmpl-2","object":"chat.completion","created":1700000000,"model":"gpt-4","choices":[],"usage":{"prompt_tokens":10,"completion_tokens":0,"total_tokens":10}}"#
+        )?;
+        let response = ChatResponse::from(completion);
+
+        assert_eq!(response.content, "");
+        assert!(!response.is_complete);
+        assert!(response.choices.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_chunk_to_deltas() -> Result<()> {
+        let chunk: OpenAIChatCompletionChunk = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"content":"Rust"},"finish_reason":null}]}"#
+        )?;
+        let deltas = chunk_to_deltas(chunk);
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].content, "Rust");
+        assert!(!deltas[0].is_end);
+
+        // Several choices in a chunk
+        let chunk: OpenAIChatCompletionChunk = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{},"finish_reason":"stop"},{"index":1,"delta":{"content":"!"},"finish_reason":null}]}"#
+        )?;
+        let deltas = chunk_to_deltas(chunk);
+        assert_eq!(deltas.len(), 2);
+        assert_eq!((deltas[0].index, deltas[0].content.as_str(), deltas[0].is_end), (0, "", true));
+        assert_eq!(deltas[0].finish_reason, Some(FinishReason::Stop));
+        assert_eq!(deltas[1].metadata.as_ref().map(|metadata| metadata.id.as_str()), Some("chatcmpl-1"));
+        assert_eq!((deltas[1].index, deltas[1].content.as_str(), deltas[1].is_end), (1, "!", false));
+
+        // Pieces of a tool call
+        let chunk: OpenAIChatCompletionChunk = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"id":"call-1","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#
+        )?;
+        let deltas = chunk_to_deltas(chunk);
+        assert_eq!(
+            deltas[0].tool_calls,
+            vec![ChatToolCallDelta {
+                index: 0,
+                id: Some("call-1".to_string()),
+                name: Some("get_weather".to_string()),
+                arguments: String::new(),
+            }]
+        );
+        let chunk: OpenAIChatCompletionChunk = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"city\":"}}]},"finish_reason":null}]}"#
+        )?;
+        let deltas = chunk_to_deltas(chunk);
+        assert_eq!((deltas[0].tool_calls[0].name.as_deref(), deltas[0].tool_calls[0].arguments.as_str()), (None, r#"{"city":"#));
+
+        // Only the usage
+        let chunk: OpenAIChatCompletionChunk = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[],"usage":{"prompt_tokens":10,"completion_tokens":8,"total_tokens":18}}"#
+        )?;
+        let deltas = chunk_to_deltas(chunk);
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].usage.as_ref().map(|usage| usage.total_tokens), Some(18));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,149 @@
+use std::ops::RangeInclusive;
+use tracing::warn;
+use crate::{UnilangError, Result};
+use super::ChatModel;
+
+/// Parameters of a single chat request, which override those of the `ChatModel`.
+///
+/// Parameters that are set neither here nor on the model are left to the defaults of the provider.
+/// Each provider clamps the values to the range that it accepts, and maps them onto its own scale if it has one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatRequestOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub presence_penalty: Option<f32>,
+
+    /// Number of choices to generate, which is only set per request.
+    pub n: Option<u32>,
+}
+
+impl ChatRequestOptions {
+    /// Create a builder for the request options.
+    pub fn builder() -> ChatRequestOptionsBuilder {
+        ChatRequestOptionsBuilder::new()
+    }
+
+    /// Fill the parameters that are not set with those of the model.
+    pub(crate) fn or_model(&self, model: &ChatModel) -> Self {
+        Self {
+            temperature: self.temperature.or(model.temperature),
+            top_p: self.top_p.or(model.top_p),
+            presence_penalty: self.presence_penalty.or(model.presence_penalty),
+            n: self.n,
+        }
+    }
+}
+
+pub struct ChatRequestOptionsBuilder {
+    options: ChatRequestOptions,
+}
+
+impl Default for ChatRequestOptionsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChatRequestOptionsBuilder {
+    pub fn new() -> Self {
+        Self {
+            options: ChatRequestOptions::default(),
+        }
+    }
+
+    /// Set the temperature, which controls the randomness of the output.
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.options.temperature = Some(temperature);
+        self
+    }
+
+    /// Set top_p, which controls the diversity of the output via nucleus sampling.
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        self.options.top_p = Some(top_p);
+        self
+    }
+
+    /// Set the presence penalty, which penalizes tokens that already appear in the text.
+    pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
+        self.options.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Set the number of choices to generate, e.g. to pick the best of several candidates.
+    pub fn n(mut self, n: u32) -> Self {
+        self.options.n = Some(n);
+        self
+    }
+
+    pub fn build(self) -> ChatRequestOptions {
+        self.options
+    }
+}
+
+/// Clamp a parameter to the range that the provider accepts.
+///
+/// Values out of the range are clamped with a warning, and values that are not numbers are rejected.
+pub(crate) fn clamp_parameter(name: &str, value: Option<f32>, range: RangeInclusive<f32>) -> Result<Option<f32>> {
+    let value = match value {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    if !value.is_finite() {
+        return Err(UnilangError::InvalidInput(
+            format!("{} must be a finite number, but it is {}", name, value)
+        ));
+    }
+
+    let clamped = value.clamp(*range.start(), *range.end());
+    if clamped != value {
+        warn!("{} {} is clamped to {}, since it is out of the range {:?}", name, value, clamped, range);
+    }
+
+    Ok(Some(clamped))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        UnilangError,
+        chat::ChatModel,
+    };
+    use super::{
+        clamp_parameter,
+        ChatRequestOptions,
+    };
+
+    #[test]
+    fn test_or_model() {
+        let model = ChatModel::builder()
+            .temperature(0.5)
+            .top_p(0.75)
+            .build();
+        let options = ChatRequestOptions::builder()
+            .temperature(0.1)
+            .build();
+
+        assert_eq!(
+            options.or_model(&model),
+            ChatRequestOptions {
+                temperature: Some(0.1),
+                top_p: Some(0.75),
+                presence_penalty: None,
+                n: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_clamp_parameter() {
+        assert_eq!(clamp_parameter("temperature", None, 0.0..=2.0).unwrap(), None);
+        assert_eq!(clamp_parameter("temperature", Some(0.5), 0.0..=2.0).unwrap(), Some(0.5));
+        assert_eq!(clamp_parameter("temperature", Some(2.5), 0.0..=2.0).unwrap(), Some(2.0));
+        assert_eq!(clamp_parameter("temperature", Some(-1.0), 0.0..=2.0).unwrap(), Some(0.0));
+        assert!(matches!(
+            clamp_parameter("temperature", Some(f32::NAN), 0.0..=2.0),
+            Err(UnilangError::InvalidInput(_))
+        ));
+    }
+}
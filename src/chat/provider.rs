@@ -0,0 +1,91 @@
+use std::fmt::Debug;
+use async_trait::async_trait;
+use futures::stream;
+use crate::Result;
+use super::{
+    ChatModel,
+    ChatMessage,
+    ChatRequestOptions,
+    ChatResponse,
+    ChatResponseDelta,
+    ChatResponseStream,
+    ChatToolCall,
+    ChatToolCallDelta,
+};
+
+/// A backend that serves the chat requests of a `ChatModel`.
+/// 
+/// OpenAI and Qianfan are supported out of the box.
+/// Implement this trait to plug in other backends.
+#[async_trait]
+pub trait ChatProvider: Debug + Send + Sync {
+    /// Call the backend and return a complete chat response.
+    /// 
+    /// The options override the parameters of the model for this request.
+    async fn get_complete_chat_response(
+        &self,
+        model: &ChatModel,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponse>;
+
+    /// Call the backend and return a stream of chat response deltas.
+    /// 
+    /// Backends without streaming support can rely on the default implementation,
+    /// which yields each choice of the complete response as a single delta.
+    async fn get_streamed_chat_response(
+        &self,
+        model: &ChatModel,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponseStream> {
+        let response = self.get_complete_chat_response(model, messages, options).await?;
+
+        let mut deltas = match response.choices.is_empty() {
+            true => vec![
+                ChatResponseDelta {
+                    content: response.content,
+                    is_end: true,
+                    index: 0,
+                    finish_reason: response.finish_reason,
+                    tool_calls: tool_call_deltas(response.tool_calls),
+                    usage: None,
+                    metadata: None,
+                },
+            ],
+            false => response.choices
+                .into_iter()
+                .map(|choice| ChatResponseDelta {
+                    content: choice.content,
+                    is_end: true,
+                    index: choice.index,
+                    finish_reason: choice.finish_reason,
+                    tool_calls: tool_call_deltas(choice.tool_calls),
+                    usage: None,
+                    metadata: None,
+                })
+                .collect(),
+        };
+
+        // The usage covers all the choices, so it is only attached to the last delta
+        if let Some(delta) = deltas.last_mut() {
+            delta.usage = Some(response.usage);
+        }
+        if let Some(delta) = deltas.first_mut() {
+            delta.metadata = Some(response.metadata);
+        }
+
+        Ok(
+            ChatResponseStream::new(stream::iter(deltas.into_iter().map(Ok)))
+        )
+    }
+}
+
+/// Split the tool calls of a complete response into pieces, one for each call.
+fn tool_call_deltas(tool_calls: Vec<ChatToolCall>) -> Vec<ChatToolCallDelta> {
+    tool_calls
+        .into_iter()
+        .zip(0..)
+        .map(|(tool_call, index)| ChatToolCallDelta::whole(index, tool_call))
+        .collect()
+}
@@ -1,28 +1,759 @@
-// use anyhow::{Result, anyhow};
-// use crate::{
-//     chat::{
-//         ChatModel, 
-//         ChatModelName, 
-//         ChatMessage,
-//         ChatRole,
-//         ChatResponse,
-//         ChatTokenUsage,
-//     },
-//     qianfan::{
-//         self,
-//         chat::{
-//             QianFanChatRequestBody, 
-//             QianFanChatCompletion, 
-//             QianFanChatMessage,
-//             QianFanChatRole,
-//         }
-//     },
-// };
-
-// pub async fn get_complete_chat_response(
-//     model: &ChatModel,
-//     messages: Vec<ChatMessage>,
-// ) -> Result<ChatResponse> {
-//     // Get the model name
-//     let model_name = chat_model_name_to_string(&model.name)?;
-// }
\ No newline at end of file
+use async_trait::async_trait;
+use futures::StreamExt;
+use crate::{
+    UnilangError,
+    Result,
+    chat::{
+        ChatModel, 
+        ChatModelName,
+        ChatMessage,
+        ChatProvider,
+        ChatRequestOptions,
+        ChatRole,
+        ChatChoice,
+        ChatResponse,
+        ChatResponseDelta,
+        ChatResponseMetadata,
+        ChatResponseStream,
+        ChatTokenUsage,
+        ChatToolCall,
+        ChatToolCallDelta,
+        FinishReason,
+        options::clamp_parameter,
+    },
+    qianfan::{
+        self,
+        chat::{
+            QianfanChatFunction,
+            QianfanChatModelName,
+            QianfanChatRequestBody,
+            QianfanChatResponse,
+            QianfanChatMessage,
+            QianfanChatRole,
+            QianfanFunctionCall,
+        },
+    },
+};
+
+/// The name of Qianfan in the metadata of responses.
+const PROVIDER_NAME: &str = "qianfan";
+
+/// The provider that serves chat requests with Qianfan's chat API.
+#[derive(Debug, Default)]
+pub struct QianfanChatProvider;
+
+#[async_trait]
+impl ChatProvider for QianfanChatProvider {
+    async fn get_complete_chat_response(
+        &self,
+        model: &ChatModel,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponse> {
+        // Get the model name
+        let model_name = chat_model_name_to_qianfan_model_name(&model.name)?;
+
+        let api_base = model.api_base.as_deref().unwrap_or(qianfan::QIANFAN_API_BASE);
+        let credentials = model.credential_provider.get_credentials().await?;
+        let request_body = create_request_body(model, messages, options)?;
+
+        // Call API to get chat response
+        let mut response = ChatResponse::from(
+            qianfan::chat::get_complete_chat_response(
+                &model.client,
+                api_base,
+                &credentials,
+                model_name,
+                &request_body,
+            ).await?
+        );
+        response.metadata.model = model_name.as_str().to_string();
+
+        Ok(response)
+    }
+
+    async fn get_streamed_chat_response(
+        &self,
+        model: &ChatModel,
+        messages: Vec<ChatMessage>,
+        options: &ChatRequestOptions,
+    ) -> Result<ChatResponseStream> {
+        // Get the model name
+        let model_name = chat_model_name_to_qianfan_model_name(&model.name)?;
+
+        let api_base = model.api_base.as_deref().unwrap_or(qianfan::QIANFAN_API_BASE);
+        let credentials = model.credential_provider.get_credentials().await?;
+        let request_body = create_request_body(model, messages, options)?;
+
+        // Call API to get the streamed chat response
+        let stream = qianfan::chat::get_streamed_chat_response(
+            &model.client,
+            api_base,
+            &credentials,
+            model_name,
+            &request_body,
+        ).await?;
+
+        Ok(
+            ChatResponseStream::new(stream.map(move |response| response.map(|response| {
+                let mut delta = ChatResponseDelta::from(response);
+                if let Some(metadata) = &mut delta.metadata {
+                    metadata.model = model_name.as_str().to_string();
+                }
+
+                delta
+            })))
+        )
+    }
+}
+
+/// The smallest temperature sent to Qianfan, which rejects 0.
+const MIN_TEMPERATURE: f32 = 0.01;
+
+/// Map a presence penalty from 0 to 2 onto Qianfan's penalty score, which ranges from 1 to 2.
+///
+/// No penalty is 1 for Qianfan, which cannot favour repeated tokens as a negative presence penalty does.
+fn presence_penalty_to_penalty_score(presence_penalty: f32) -> f32 {
+    1.0 + presence_penalty / 2.0
+}
+
+/// Create the request body of Qianfan's chat API.
+fn create_request_body(
+    model: &ChatModel,
+    messages: Vec<ChatMessage>,
+    options: &ChatRequestOptions,
+) -> Result<QianfanChatRequestBody> {
+    // Qianfan generates a single choice
+    if options.n.is_some_and(|n| n != 1) {
+        return Err(UnilangError::InvalidInput(
+            "Qianfan only generates a single choice, so n must be 1".to_string()
+        ));
+    }
+
+    let mut builder = QianfanChatRequestBody::builder()
+        .messages(
+            messages
+            .iter()
+            .map(chat_message_to_qianfan_message)
+            .collect::<Result<Vec<_>>>()?
+        );
+
+    // Parameters that are set neither for the request nor for the model
+    // are left to the defaults of the builder.
+    let options = options.or_model(model);
+    if let Some(temperature) = clamp_parameter("temperature", options.temperature, MIN_TEMPERATURE..=1.0)? {
+        builder = builder.temperature(temperature);
+    }
+    if let Some(top_p) = clamp_parameter("top_p", options.top_p, 0.0..=1.0)? {
+        builder = builder.top_p(top_p);
+    }
+    if let Some(presence_penalty) = clamp_parameter("presence_penalty", options.presence_penalty, 0.0..=2.0)? {
+        builder = builder.penalty_score(presence_penalty_to_penalty_score(presence_penalty));
+    }
+
+    // Tools are called as functions by ERNIE-Bot
+    let builder = match model.tools.is_empty() {
+        true => builder,
+        false => builder.functions(
+            model.tools
+                .iter()
+                .map(|tool| QianfanChatFunction {
+                    name: tool.name.to_owned(),
+                    description: tool.description.to_owned(),
+                    parameters: tool.parameters.to_owned(),
+                    responses: None,
+                    examples: None,
+                })
+                .collect()
+        ),
+    };
+
+    // Qianfan takes the profile as the system field instead of a message
+    match &model.profile {
+        Some(profile) => Ok(builder.system(profile).build()),
+        None => Ok(builder.build()),
+    }
+}
+
+/// Convert a `ChatMessage` to a message of Qianfan's chat API.
+///
+/// An error is returned if an assistant message calls more than one tool,
+/// since ERNIE-Bot calls at most one function in a message,
+/// or if a tool message does not tell which function it responds to.
+fn chat_message_to_qianfan_message(message: &ChatMessage) -> Result<QianfanChatMessage> {
+    match message.role {
+        ChatRole::User => Ok(QianfanChatMessage::new(QianfanChatRole::User, &message.content)),
+        ChatRole::Assistant => match message.tool_calls.as_deref().unwrap_or_default() {
+            [] => Ok(QianfanChatMessage::new(QianfanChatRole::Assistant, &message.content)),
+            [tool_call] => Ok(QianfanChatMessage::function_call(QianfanFunctionCall {
+                name: tool_call.name.to_owned(),
+                arguments: tool_call.arguments.to_owned(),
+                thoughts: None,
+            })),
+            tool_calls => Err(UnilangError::InvalidInput(format!(
+                "Qianfan calls at most one function in a message, but {} tool calls are given",
+                tool_calls.len(),
+            ))),
+        },
+        ChatRole::Tool => match &message.tool_name {
+            Some(tool_name) => Ok(QianfanChatMessage::function_result(tool_name, &message.content)),
+            None => Err(UnilangError::InvalidInput(
+                "a tool message must have a tool_name".to_string()
+            )),
+        },
+    }
+}
+
+/// Convert a `ChatModelName` to a `QianfanChatModelName`.
+fn chat_model_name_to_qianfan_model_name(model_name: &ChatModelName) -> Result<QianfanChatModelName> {
+    match model_name {
+        ChatModelName::QianfanErnieBot4 => Ok(QianfanChatModelName::ErnieBot4),
+        ChatModelName::QianfanErnieBot => Ok(QianfanChatModelName::ErnieBot),
+        ChatModelName::QianfanErnieBotTurbo => Ok(QianfanChatModelName::ErnieBotTurbo),
+        ChatModelName::QianfanLlama2Of7BChat => Ok(QianfanChatModelName::Llama2Of7BChat),
+        ChatModelName::QianfanLlama2Of13BChat => Ok(QianfanChatModelName::Llama2Of13BChat),
+        ChatModelName::QianfanLlama2Of70BChat => Ok(QianfanChatModelName::Llama2Of70BChat),
+        ChatModelName::QianfanChineseLlama2Of7B => Ok(QianfanChatModelName::QianfanChineseLlama2Of7B),
+        _ => Err(UnilangError::UnsupportedModel(
+            format!("{:?} is not available in Qianfan's chat models", model_name)
+        )),
+    }
+}
+
+impl From<QianfanChatResponse> for ChatResponse {
+    fn from(response: QianfanChatResponse) -> Self {
+        let finish_reason = get_finish_reason(&response);
+        let metadata = get_metadata(&response);
+
+        // Qianfan does not identify function calls, so the response ID is used instead
+        let tool_calls = response.function_call
+            .map(|function_call| ChatToolCall {
+                id: response.id,
+                name: function_call.name,
+                arguments: function_call.arguments,
+            })
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        Self {
+            content: response.result.to_owned(),
+            is_complete: !finish_reason.is_truncated(),
+            finish_reason: Some(finish_reason.to_owned()),
+            usage: ChatTokenUsage {
+                prompt_tokens: response.usage.prompt_tokens,
+                completion_tokens: response.usage.completion_tokens,
+                total_tokens: response.usage.total_tokens,
+            },
+            tool_calls: tool_calls.to_owned(),
+            choices: vec![
+                ChatChoice {
+                    index: 0,
+                    content: response.result,
+                    finish_reason: Some(finish_reason),
+                    tool_calls,
+                },
+            ],
+            metadata,
+        }
+    }
+}
+
+impl From<QianfanChatResponse> for ChatResponseDelta {
+    fn from(response: QianfanChatResponse) -> Self {
+        let is_end = response.is_end.unwrap_or(false);
+
+        Self {
+            finish_reason: match is_end {
+                true => Some(get_finish_reason(&response)),
+                false => None,
+            },
+            metadata: Some(get_metadata(&response)),
+            // Qianfan does not identify function calls, so the response ID is used instead
+            tool_calls: response.function_call
+                .map(|function_call| ChatToolCallDelta::whole(0, ChatToolCall {
+                    id: response.id,
+                    name: function_call.name,
+                    arguments: function_call.arguments,
+                }))
+                .into_iter()
+                .collect(),
+            content: response.result,
+            is_end,
+            index: 0,
+            usage: Some(ChatTokenUsage {
+                prompt_tokens: response.usage.prompt_tokens,
+                completion_tokens: response.usage.completion_tokens,
+                total_tokens: response.usage.total_tokens,
+            }),
+        }
+    }
+}
+
+/// Get the finish reason of a response, which Qianfan does not report as such.
+fn get_finish_reason(response: &QianfanChatResponse) -> FinishReason {
+    if response.need_clear_history {
+        FinishReason::SafetyCleared
+    } else if response.function_call.is_some() {
+        FinishReason::ToolCalls
+    } else if response.is_truncated {
+        FinishReason::Length
+    } else {
+        FinishReason::Stop
+    }
+}
+
+/// Get the metadata of a response.
+/// Qianfan does not return the model name, so it is left to the provider to set.
+fn get_metadata(response: &QianfanChatResponse) -> ChatResponseMetadata {
+    ChatResponseMetadata {
+        id: response.id.to_owned(),
+        provider: PROVIDER_NAME.to_string(),
+        model: String::new(),
+        created: response.created.into(),
+        latency: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use anyhow::Result;
+    use futures::StreamExt;
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        RetryPolicy,
+        StaticCredentialProvider,
+        UnilangError,
+        qianfan::mock_access_token,
+        chat::{
+            ChatModel,
+            ChatModelName,
+            ChatMessage,
+            ChatProvider,
+            ChatRequestOptions,
+            ChatResponse,
+            ChatResponseDelta,
+            ChatRole,
+            ChatTool,
+            ChatToolCall,
+            FinishReason,
+        },
+        qianfan::chat::QianfanChatResponse,
+    };
+    use super::{
+        create_request_body,
+        QianfanChatProvider,
+    };
+
+    #[tokio::test]
+    async fn test_get_complete_chat_response() -> Result<()> {
+        let response = QianfanChatProvider.get_complete_chat_response(
+            &ChatModel::builder()
+                .name(ChatModelName::QianfanErnieBotTurbo)
+                .temperature(0.1)
+                .build(),
+            vec![
+                ChatMessage::new(ChatRole::User, "What is Rust?"),
+            ],
+            &ChatRequestOptions::default(),
+        ).await?;
+
+        println!("{:#?}", response);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_streamed_chat_response_with_mock_server() -> Result<()> {
+        let content = concat!(
+            "data: {\"id\":\"as-1\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":0,\"is_end\":false,",
+            "\"is_truncated\":false,\"result\":\"Rust 是一门\",\"need_clear_history\":false,",
+            "\"usage\":{\"prompt_tokens\":4,\"completion_tokens\":0,\"total_tokens\":4}}\n\n",
+            "data: {\"id\":\"as-1\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":1,\"is_end\":true,",
+            "\"is_truncated\":false,\"result\":\"编程语言。\",\"need_clear_history\":false,",
+            "\"usage\":{\"prompt_tokens\":4,\"completion_tokens\":7,\"total_tokens\":11}}\n\n",
+        );
+
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-stream").await;
+        Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions_pro"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(content, "text/event-stream"))
+            .mount(&server)
+            .await;
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot4)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .qianfan_access_key("ak-stream")
+                    .qianfan_secret_key("sk-stream")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .build();
+
+        let deltas = model.get_streamed_chat_response(vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ]).await?
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<crate::Result<Vec<_>>>()?;
+
+        assert_eq!(deltas.iter().map(|delta| delta.content.as_str()).collect::<String>(), "Rust 是一门编程语言。");
+        assert!(!deltas[0].is_end);
+        assert!(deltas[1].is_end);
+        assert_eq!(deltas[1].finish_reason, Some(FinishReason::Stop));
+        assert_eq!(deltas[1].usage.as_ref().map(|usage| usage.total_tokens), Some(11));
+
+        let metadata = deltas[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata.provider, "qianfan");
+        assert_eq!(metadata.model, "ERNIE-Bot-4");
+
+        // The selected model is called
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[1].url.path(), "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions_pro");
+        assert_eq!(requests[1].url.query(), Some("access_token=token-stream"));
+        assert_eq!(requests[1].body_json::<serde_json::Value>()?["stream"], json!(true));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_temporary_errors() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-retry").await;
+        for response in [
+            r#"{"error_code":18,"error_msg":"Open api qps request limit reached"}"#,
+            r#"{"error_code":336100,"error_msg":"internal error"}"#,
+            r#"{"id":"as-1","object":"chat.completion","created":1700000000,"result":"Rust is a language.","is_truncated":false,"need_clear_history":false,"usage":{"prompt_tokens":3,"completion_tokens":5,"total_tokens":8}}"#,
+            r#"{"error_code":336003,"error_msg":"the length of messages must be an odd number"}"#,
+        ] {
+            Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .qianfan_access_key("ak-retry")
+                    .qianfan_secret_key("sk-retry")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .retry_policy(
+                RetryPolicy::builder()
+                    .max_attempts(3)
+                    .initial_backoff(Duration::from_millis(1))
+                    .build()?
+            )
+            .build();
+        let messages = vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ];
+
+        let response = model.get_complete_chat_response(messages.clone()).await?;
+        assert_eq!(response.content, "Rust is a language.");
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+        assert_eq!(response.metadata.id, "as-1");
+        assert_eq!(response.metadata.model, "ERNIE-Bot");
+        assert_eq!(response.metadata.created, 1700000000);
+        assert!(response.metadata.latency.is_some());
+
+        // Invalid requests are not retried
+        match model.get_complete_chat_response(messages).await {
+            Err(UnilangError::Qianfan(error)) => assert_eq!(error.error_code, 336003),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(server.received_requests().await.unwrap().len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_retry_unavailable_server() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-unavailable").await;
+        for (path_name, response, content_type) in [
+            ("completions", r#"{"id":"as-1","object":"chat.completion","created":1700000000,"result":"Rust is a language.","is_truncated":false,"need_clear_history":false,"usage":{"prompt_tokens":3,"completion_tokens":5,"total_tokens":8}}"#, "application/json"),
+            ("eb-instant", "data: {\"id\":\"as-2\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":0,\"is_end\":true,\"is_truncated\":false,\"result\":\"Rust is a language.\",\"need_clear_history\":false,\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":5,\"total_tokens\":8}}\n\n", "text/event-stream"),
+        ] {
+            // The gateway rejects the first request with a plain text body
+            let api_path = format!("/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/{}", path_name);
+            Mock::given(path(api_path.as_str()))
+                .respond_with(ResponseTemplate::new(503).set_body_raw("Service Unavailable", "text/plain"))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(path(api_path.as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(response, content_type))
+                .mount(&server)
+                .await;
+        }
+        let retry_policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(1))
+            .build()?;
+        let create_model = |name: ChatModelName| ChatModel::builder()
+            .name(name)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .qianfan_access_key("ak-unavailable")
+                    .qianfan_secret_key("sk-unavailable")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .retry_policy(retry_policy.clone())
+            .build();
+        let messages = vec![
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+        ];
+
+        let response = create_model(ChatModelName::QianfanErnieBot)
+            .get_complete_chat_response(messages.clone())
+            .await?;
+        assert_eq!(response.content, "Rust is a language.");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        let content = create_model(ChatModelName::QianfanErnieBotTurbo)
+            .get_streamed_chat_response(messages)
+            .await?
+            .map(|delta| delta.map(|delta| delta.content))
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<crate::Result<String>>()?;
+        assert_eq!(content, "Rust is a language.");
+        assert_eq!(server.received_requests().await.unwrap().len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_body() -> Result<()> {
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot4)
+            .temperature(0.5)
+            .top_p(0.75)
+            .presence_penalty(1.5)
+            .profile("You are a helpful assistant.")
+            .build();
+
+        let request_body = create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "What is Rust?"),
+            ],
+            &ChatRequestOptions::default(),
+        )?;
+
+        assert_eq!(
+            serde_json::to_value(request_body)?,
+            json!({
+                "messages": [{"role": "user", "content": "What is Rust?"}],
+                "temperature": 0.5,
+                "top_p": 0.75,
+                "penalty_score": 1.75,
+                "system": "You are a helpful assistant.",
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clamp_parameters() -> Result<()> {
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot)
+            .temperature(1.5)
+            .build();
+        let options = ChatRequestOptions::builder()
+            .top_p(0.5)
+            .presence_penalty(-1.0)
+            .build();
+
+        let request_body = serde_json::to_value(create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "What is Rust?"),
+            ],
+            &options,
+        )?)?;
+        assert_eq!(request_body["temperature"], json!(1.0));
+        assert_eq!(request_body["top_p"], json!(0.5));
+        // A negative presence penalty is no penalty for Qianfan
+        assert_eq!(request_body["penalty_score"], json!(1.0));
+
+        // Qianfan rejects a temperature of 0
+        let options = ChatRequestOptions::builder()
+            .temperature(0.0)
+            .build();
+        let request_body = create_request_body(&model, vec![], &options)?;
+        assert_eq!(request_body.temperature, 0.01);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_several_choices() {
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot)
+            .build();
+        let options = ChatRequestOptions::builder()
+            .n(2)
+            .build();
+
+        assert!(matches!(
+            create_request_body(&model, vec![], &options),
+            Err(UnilangError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_convert_response() -> Result<()> {
+        let content = r#"{"id":"as-1","object":"chat.completion","created":1700000000,"is_truncated":true,"result":"Rust is","need_clear_history":false,"usage":{"prompt_tokens":3,"completion_tokens":2,"total_tokens":5}}"#;
+
+        let response = ChatResponse::from(serde_json::from_str::<QianfanChatResponse>(content)?);
+        assert_eq!(response.content, "Rust is");
+        assert!(!response.is_complete);
+        assert_eq!(response.finish_reason, Some(FinishReason::Length));
+        assert_eq!(response.usage.total_tokens, 5);
+
+        // The input is judged unsafe
+        let content = r#"{"id":"as-2","object":"chat.completion","created":1700000000,"is_truncated":false,"result":"","need_clear_history":true,"ban_round":-1,"usage":{"prompt_tokens":3,"completion_tokens":0,"total_tokens":3}}"#;
+
+        let response = ChatResponse::from(serde_json::from_str::<QianfanChatResponse>(content)?);
+        assert!(!response.is_complete);
+        assert_eq!(response.finish_reason, Some(FinishReason::SafetyCleared));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_body_with_tools() -> Result<()> {
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot)
+            .temperature(0.5)
+            .top_p(0.75)
+            .presence_penalty(1.5)
+            .tool(ChatTool::new(
+                "get_weather",
+                "Get the current weather of a city",
+                json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+            ))
+            .build();
+        let tool_call = ChatToolCall {
+            id: "as-1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: r#"{"city":"Beijing"}"#.to_string(),
+        };
+
+        let request_body = create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "How is the weather in Beijing?"),
+                ChatMessage::tool_calls(vec![tool_call.clone()]),
+                ChatMessage::tool_result(&tool_call, "Sunny"),
+            ],
+            &ChatRequestOptions::default(),
+        )?;
+
+        assert_eq!(
+            serde_json::to_value(request_body)?,
+            json!({
+                "messages": [
+                    {"role": "user", "content": "How is the weather in Beijing?"},
+                    {
+                        "role": "assistant",
+                        "content": "",
+                        "function_call": {"name": "get_weather", "arguments": "{\"city\":\"Beijing\"}"},
+                    },
+                    {"role": "function", "content": "Sunny", "name": "get_weather"},
+                ],
+                "temperature": 0.5,
+                "top_p": 0.75,
+                "penalty_score": 1.75,
+                "functions": [{
+                    "name": "get_weather",
+                    "description": "Get the current weather of a city",
+                    "parameters": {"type": "object", "properties": {"city": {"type": "string"}}},
+                }],
+            })
+        );
+
+        // Qianfan cannot be given several tool calls in a message
+        let result = create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "How is the weather in Beijing and Shanghai?"),
+                ChatMessage::tool_calls(vec![
+                    tool_call.clone(),
+                    ChatToolCall {
+                        id: "as-2".to_string(),
+                        name: "get_weather".to_string(),
+                        arguments: r#"{"city":"Shanghai"}"#.to_string(),
+                    },
+                ]),
+            ],
+            &ChatRequestOptions::default(),
+        );
+        assert!(matches!(result, Err(UnilangError::InvalidInput(_))));
+
+        // A tool message must tell which function it responds to
+        let result = create_request_body(
+            &model,
+            vec![
+                ChatMessage::new(ChatRole::User, "How is the weather in Beijing?"),
+                ChatMessage::tool_calls(vec![tool_call.clone()]),
+                ChatMessage::new(ChatRole::Tool, "Sunny"),
+            ],
+            &ChatRequestOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(UnilangError::InvalidInput(message)) if message.contains("tool_name")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_response_with_function_call() -> Result<()> {
+        let content = r#"{"id":"as-1","object":"chat.completion","created":1700000000,"is_truncated":false,"need_clear_history":false,"function_call":{"name":"get_weather","arguments":"{\"city\":\"Beijing\"}","thoughts":"I need the weather."},"usage":{"prompt_tokens":10,"completion_tokens":5,"total_tokens":15}}"#;
+
+        let response = ChatResponse::from(serde_json::from_str::<QianfanChatResponse>(content)?);
+        assert_eq!(response.content, "");
+        assert_eq!(
+            response.tool_calls,
+            vec![ChatToolCall {
+                id: "as-1".to_string(),
+                name: "get_weather".to_string(),
+                arguments: r#"{"city":"Beijing"}"#.to_string(),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_response_to_delta() -> Result<()> {
+        let response: QianfanChatResponse = serde_json::from_str(
+            r#"{"id":"as-1","object":"chat.completion","created":1700000000,"sentence_id":0,"is_end":false,"is_truncated":false,"result":"Rust","need_clear_history":false,"usage":{"prompt_tokens":3,"completion_tokens":1,"total_tokens":4}}"#
+        )?;
+        let delta = ChatResponseDelta::from(response);
+
+        assert_eq!(delta.content, "Rust");
+        assert!(!delta.is_end);
+        assert_eq!(delta.usage.unwrap().total_tokens, 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, BTreeSet};
+use super::{
+    super::ChatToolCall,
+    ChatChoice,
+    ChatResponse,
+    ChatResponseDelta,
+    ChatResponseMetadata,
+    ChatTokenUsage,
+};
+
+/// Folds the deltas of a streamed chat response back into a `ChatResponse`.
+///
+/// The deltas of several choices are told apart by their index,
+/// and so are the pieces of several tool calls in a choice.
+/// Use it when the deltas are consumed one by one,
+/// or `ChatResponseStream::collect_response` to consume the whole stream.
+#[derive(Debug, Default)]
+pub struct ChatResponseAggregator {
+    choices: BTreeMap<u32, ChatChoice>,
+    ended_choices: BTreeSet<u32>,
+
+    /// Tool calls keyed by the index of the choice and the index of the call in the choice.
+    tool_calls: BTreeMap<(u32, u32), ChatToolCall>,
+    usage: Option<ChatTokenUsage>,
+    metadata: Option<ChatResponseMetadata>,
+}
+
+impl ChatResponseAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next delta of the response.
+    pub fn push(&mut self, delta: &ChatResponseDelta) {
+        let choice = self.choices
+            .entry(delta.index)
+            .or_insert_with(|| ChatChoice {
+                index: delta.index,
+                content: String::new(),
+                finish_reason: None,
+                tool_calls: vec![],
+            });
+        choice.content.push_str(&delta.content);
+        if delta.finish_reason.is_some() {
+            choice.finish_reason = delta.finish_reason.to_owned();
+        }
+        if delta.is_end {
+            self.ended_choices.insert(delta.index);
+        }
+
+        // The arguments of a tool call are joined, while the ID and name are only sent once
+        for tool_call_delta in &delta.tool_calls {
+            let tool_call = self.tool_calls
+                .entry((delta.index, tool_call_delta.index))
+                .or_insert_with(|| ChatToolCall {
+                    id: String::new(),
+                    name: String::new(),
+                    arguments: String::new(),
+                });
+            if let Some(id) = &tool_call_delta.id {
+                tool_call.id = id.to_owned();
+            }
+            if let Some(name) = &tool_call_delta.name {
+                tool_call.name = name.to_owned();
+            }
+            tool_call.arguments.push_str(&tool_call_delta.arguments);
+        }
+
+        // The usage reported last covers the whole response
+        if let Some(usage) = &delta.usage {
+            self.usage = Some(usage.clone());
+        }
+
+        if self.metadata.is_none() {
+            self.metadata = delta.metadata.clone();
+        }
+    }
+
+    /// Get the content of the first choice received so far.
+    pub fn content(&self) -> &str {
+        self.choices
+            .values()
+            .next()
+            .map(|choice| choice.content.as_str())
+            .unwrap_or_default()
+    }
+
+    /// Get the content of the choice with the index received so far.
+    pub fn choice_content(&self, index: u32) -> Option<&str> {
+        self.choices
+            .get(&index)
+            .map(|choice| choice.content.as_str())
+    }
+
+    /// Build the response from the deltas received so far.
+    ///
+    /// The response is complete only if the last delta of every choice has been received
+    /// and no choice is cut short, and the usage is zero if the provider never reported it.
+    pub fn finish(mut self) -> ChatResponse {
+        for ((index, _), tool_call) in self.tool_calls {
+            if let Some(choice) = self.choices.get_mut(&index) {
+                choice.tool_calls.push(tool_call);
+            }
+        }
+
+        let is_complete = !self.choices.is_empty()
+            && self.choices.values().all(|choice| {
+                self.ended_choices.contains(&choice.index)
+                    && !matches!(&choice.finish_reason, Some(reason) if reason.is_truncated())
+            });
+        let choices = self.choices.into_values().collect::<Vec<_>>();
+        let first_choice = choices.first();
+
+        ChatResponse {
+            content: first_choice
+                .map(|choice| choice.content.to_owned())
+                .unwrap_or_default(),
+            is_complete,
+            finish_reason: first_choice.and_then(|choice| choice.finish_reason.to_owned()),
+            usage: self.usage.unwrap_or_default(),
+            tool_calls: first_choice
+                .map(|choice| choice.tool_calls.to_owned())
+                .unwrap_or_default(),
+            metadata: self.metadata.unwrap_or_default(),
+            choices,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ChatResponseAggregator,
+        ChatResponseDelta,
+        ChatResponseMetadata,
+        ChatTokenUsage,
+    };
+    use crate::chat::{
+        ChatToolCall,
+        ChatToolCallDelta,
+        FinishReason,
+    };
+
+    #[test]
+    fn test_aggregate_deltas() {
+        let mut aggregator = ChatResponseAggregator::new();
+        let deltas = [
+            ChatResponseDelta {
+                content: "Rust is ".to_string(),
+                is_end: false,
+                index: 0,
+                finish_reason: None,
+                tool_calls: vec![],
+                usage: Some(ChatTokenUsage {
+                    prompt_tokens: 4,
+                    completion_tokens: 2,
+                    total_tokens: 6,
+                }),
+                metadata: Some(ChatResponseMetadata {
+                    id: "chatcmpl-1".to_string(),
+                    provider: "openai".to_string(),
+                    model: "gpt-4-0613".to_string(),
+                    created: 1700000000,
+                    latency: None,
+                }),
+            },
+            ChatResponseDelta {
+                content: "a language.".to_string(),
+                is_end: true,
+                index: 0,
+                finish_reason: Some(FinishReason::Stop),
+                tool_calls: vec![],
+                usage: Some(ChatTokenUsage {
+                    prompt_tokens: 4,
+                    completion_tokens: 5,
+                    total_tokens: 9,
+                }),
+                metadata: None,
+            },
+        ];
+
+        aggregator.push(&deltas[0]);
+        assert_eq!(aggregator.content(), "Rust is ");
+
+        aggregator.push(&deltas[1]);
+        let response = aggregator.finish();
+
+        assert_eq!(response.content, "Rust is a language.");
+        assert!(response.is_complete);
+        assert_eq!(response.choices.len(), 1);
+        assert_eq!(response.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(response.metadata.id, "chatcmpl-1");
+        assert_eq!(response.usage.completion_tokens, 5);
+        assert_eq!(response.usage.total_tokens, 9);
+    }
+
+    #[test]
+    fn test_aggregate_incomplete_deltas() {
+        let mut aggregator = ChatResponseAggregator::new();
+        aggregator.push(&ChatResponseDelta {
+            content: "Rust".to_string(),
+            is_end: false,
+            index: 0,
+            finish_reason: None,
+            tool_calls: vec![],
+            usage: None,
+            metadata: None,
+        });
+        let response = aggregator.finish();
+
+        assert!(!response.is_complete);
+        assert_eq!(response.usage.total_tokens, 0);
+        assert_eq!(response.metadata.id, "");
+
+        // Cut off at the maximum number of tokens
+        let mut aggregator = ChatResponseAggregator::new();
+        aggregator.push(&ChatResponseDelta {
+            content: "Rust".to_string(),
+            is_end: true,
+            index: 0,
+            finish_reason: Some(FinishReason::Length),
+            tool_calls: vec![],
+            usage: None,
+            metadata: None,
+        });
+        let response = aggregator.finish();
+
+        assert!(!response.is_complete);
+        assert_eq!(response.finish_reason, Some(FinishReason::Length));
+    }
+
+    #[test]
+    fn test_aggregate_interleaved_choices() {
+        let delta = |index, content: &str, finish_reason: Option<FinishReason>| ChatResponseDelta {
+            content: content.to_string(),
+            is_end: finish_reason.is_some(),
+            index,
+            finish_reason,
+            tool_calls: vec![],
+            usage: None,
+            metadata: None,
+        };
+
+        let mut aggregator = ChatResponseAggregator::new();
+        aggregator.push(&delta(1, "Rust is ", None));
+        aggregator.push(&delta(0, "Rust ", None));
+        aggregator.push(&delta(0, "rocks.", Some(FinishReason::Stop)));
+        aggregator.push(&delta(1, "fast", None));
+
+        assert_eq!(aggregator.choice_content(0), Some("Rust rocks."));
+        assert_eq!(aggregator.choice_content(1), Some("Rust is fast"));
+        assert_eq!(aggregator.choice_content(2), None);
+
+        // The second choice is cut off
+        let response = aggregator.finish();
+        assert!(!response.is_complete);
+        assert_eq!(response.content, "Rust rocks.");
+        assert_eq!(
+            response.choices
+                .iter()
+                .map(|choice| (choice.index, choice.finish_reason.to_owned()))
+                .collect::<Vec<_>>(),
+            vec![(0, Some(FinishReason::Stop)), (1, None)]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_tool_calls() {
+        let delta = |tool_calls: Vec<ChatToolCallDelta>, finish_reason: Option<FinishReason>| ChatResponseDelta {
+            content: String::new(),
+            is_end: finish_reason.is_some(),
+            index: 0,
+            finish_reason,
+            tool_calls,
+            usage: None,
+            metadata: None,
+        };
+        let piece = |index, id: Option<&str>, name: Option<&str>, arguments: &str| ChatToolCallDelta {
+            index,
+            id: id.map(str::to_string),
+            name: name.map(str::to_string),
+            arguments: arguments.to_string(),
+        };
+
+        // The arguments of two calls are split and interleaved
+        let mut aggregator = ChatResponseAggregator::new();
+        aggregator.push(&delta(vec![piece(0, Some("call-1"), Some("get_weather"), "")], None));
+        aggregator.push(&delta(vec![piece(0, None, None, r#"{"city":"#)], None));
+        aggregator.push(&delta(vec![piece(1, Some("call-2"), Some("get_weather"), r#"{"city":"#)], None));
+        aggregator.push(&delta(vec![piece(0, None, None, r#""Paris"}"#), piece(1, None, None, r#""Rome"}"#)], None));
+        aggregator.push(&delta(vec![], Some(FinishReason::ToolCalls)));
+        let response = aggregator.finish();
+
+        let tool_calls = vec![
+            ChatToolCall {
+                id: "call-1".to_string(),
+                name: "get_weather".to_string(),
+                arguments: r#"{"city":"Paris"}"#.to_string(),
+            },
+            ChatToolCall {
+                id: "call-2".to_string(),
+                name: "get_weather".to_string(),
+                arguments: r#"{"city":"Rome"}"#.to_string(),
+            },
+        ];
+        assert!(response.is_complete);
+        assert_eq!(response.tool_calls, tool_calls);
+        assert_eq!(response.choices[0].tool_calls, tool_calls);
+    }
+}
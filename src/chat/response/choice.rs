@@ -0,0 +1,20 @@
+use serde::Deserialize;
+use super::{
+    super::ChatToolCall,
+    FinishReason,
+};
+
+/// One of the candidate replies in a chat response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatChoice {
+    /// Position of the choice among the candidates.
+    pub index: u32,
+
+    pub content: String,
+
+    /// Why the model stopped generating this choice.
+    pub finish_reason: Option<FinishReason>,
+
+    /// Tools that the model requests to call.
+    pub tool_calls: Vec<ChatToolCall>,
+}
@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+/// Why the model stopped generating a choice, unified across the providers.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum FinishReason {
+    /// The model finished the reply or reached a stop sequence.
+    Stop,
+
+    /// The reply was cut off at the maximum number of tokens.
+    Length,
+
+    /// The reply was withheld or cut off by the content filter.
+    ContentFilter,
+
+    /// The model requests to call tools.
+    ToolCalls,
+
+    /// The input was judged unsafe, and the history should be cleared before the conversation goes on.
+    /// Qianfan reports it with `need_clear_history`.
+    SafetyCleared,
+
+    /// A reason that is unknown to this crate, as the provider reports it.
+    Other(String),
+}
+
+impl FinishReason {
+    /// Whether the reply was cut short instead of being finished by the model.
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, Self::Length | Self::ContentFilter | Self::SafetyCleared)
+    }
+}
+
+impl From<&str> for FinishReason {
+    fn from(reason: &str) -> Self {
+        match reason {
+            "stop" => Self::Stop,
+            "length" => Self::Length,
+            "content_filter" => Self::ContentFilter,
+
+            // Functions are the deprecated form of tools
+            "tool_calls" | "function_call" => Self::ToolCalls,
+
+            "safety_cleared" => Self::SafetyCleared,
+            _ => Self::Other(reason.to_string()),
+        }
+    }
+}
+
+impl From<String> for FinishReason {
+    fn from(reason: String) -> Self {
+        Self::from(reason.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FinishReason;
+
+    #[test]
+    fn test_parse_finish_reason() {
+        assert_eq!(FinishReason::from("stop"), FinishReason::Stop);
+        assert_eq!(FinishReason::from("function_call"), FinishReason::ToolCalls);
+        assert_eq!(FinishReason::from("eos"), FinishReason::Other("eos".to_string()));
+
+        assert!(FinishReason::Length.is_truncated());
+        assert!(!FinishReason::ToolCalls.is_truncated());
+    }
+}
@@ -0,0 +1,25 @@
+use std::time::Duration;
+use serde::Deserialize;
+
+/// Information about a chat response besides its content, e.g. for monitoring.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChatResponseMetadata {
+    /// ID of the response given by the provider.
+    pub id: String,
+
+    /// Name of the provider that served the request, e.g. `openai` or `qianfan`.
+    pub provider: String,
+
+    /// Name of the model that generated the response, as the provider resolves it,
+    /// e.g. `gpt-4-0613` for `gpt-4`.
+    pub model: String,
+
+    /// Unix timestamp in seconds at which the provider created the response.
+    pub created: i64,
+
+    /// Time from sending the request to receiving the whole response, retries included.
+    /// It is measured by `ChatModel`, so it is not set on the responses of providers.
+    /// A streamed response only gets it from `ChatResponseStream::collect_response`,
+    /// and otherwise it can be measured from `ChatResponseStream::started_at`.
+    pub latency: Option<Duration>,
+}
@@ -1,4 +1,17 @@
 mod response;
 pub use response::{ChatResponse, ChatTokenUsage};
 
+mod choice;
+pub use choice::ChatChoice;
+
+mod finish_reason;
+pub use finish_reason::FinishReason;
+
+mod metadata;
+pub use metadata::ChatResponseMetadata;
+
 mod stream;
+pub use stream::{ChatResponseDelta, ChatResponseStream};
+
+mod aggregator;
+pub use aggregator::ChatResponseAggregator;
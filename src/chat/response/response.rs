@@ -1,13 +1,37 @@
 use serde::Deserialize;
+use super::{
+    super::ChatToolCall,
+    ChatChoice,
+    ChatResponseMetadata,
+    FinishReason,
+};
 
+/// A complete chat response.
+///
+/// `content`, `tool_calls` and `finish_reason` are those of the first choice,
+/// and `choices` has every choice if several are requested with `n`.
 #[derive(Debug, Deserialize)]
 pub struct ChatResponse {
     pub content: String,
+
+    /// Whether every choice is finished by the model instead of being cut short.
     pub is_complete: bool,
+
+    pub finish_reason: Option<FinishReason>,
+
+    /// Token usage of all the choices.
     pub usage: ChatTokenUsage,
+
+    /// Tools that the model requests to call.
+    pub tool_calls: Vec<ChatToolCall>,
+
+    /// All the choices, ordered by index.
+    pub choices: Vec<ChatChoice>,
+
+    pub metadata: ChatResponseMetadata,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct ChatTokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
@@ -0,0 +1,120 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use futures::{Stream, StreamExt};
+use crate::Result;
+use super::{
+    super::ChatToolCallDelta,
+    ChatResponse,
+    ChatResponseAggregator,
+    ChatResponseMetadata,
+    ChatTokenUsage,
+    FinishReason,
+};
+
+/// A piece of a streamed chat response.
+///
+/// If several choices are requested with `n`, the pieces of the choices are interleaved
+/// and told apart by `index`.
+#[derive(Debug)]
+pub struct ChatResponseDelta {
+    /// Newly generated content.
+    pub content: String,
+
+    /// Whether this is the last piece of the choice.
+    pub is_end: bool,
+
+    /// Index of the choice that this piece belongs to.
+    pub index: u32,
+
+    /// Why the model stopped generating the choice, which is only set with the last piece.
+    pub finish_reason: Option<FinishReason>,
+
+    /// Pieces of the tool calls of the choice, which are joined by their index.
+    pub tool_calls: Vec<ChatToolCallDelta>,
+
+    /// Token usage of the response so far, if the provider reports it with this piece.
+    /// The last piece of OpenAI's and Qianfan's responses always has it,
+    /// and OpenAI reports it in an extra piece without content after the last piece of every choice.
+    pub usage: Option<ChatTokenUsage>,
+
+    /// Metadata of the response, which is attached to the first piece at least.
+    pub metadata: Option<ChatResponseMetadata>,
+}
+
+/// A provider-neutral stream of chat response deltas.
+/// 
+/// A failure in the middle of the response is yielded as an error,
+/// so that a truncated response can be told from a complete one.
+pub struct ChatResponseStream {
+    inner: Pin<Box<dyn Stream<Item = Result<ChatResponseDelta>> + Send>>,
+
+    /// When the request was sent, from which the latency of the collected response is measured.
+    started_at: Option<Instant>,
+}
+
+impl ChatResponseStream {
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<ChatResponseDelta>> + Send + 'static
+    {
+        Self {
+            inner: Box::pin(stream),
+            started_at: None,
+        }
+    }
+
+    /// Set when the request was sent.
+    pub(crate) fn with_started_at(mut self, started_at: Instant) -> Self {
+        self.started_at = Some(started_at);
+        self
+    }
+
+    /// Get when the request was sent, which is set by `ChatModel`.
+    ///
+    /// It measures the latency of a response whose deltas are consumed one by one,
+    /// since only `collect_response` and `collect_response_with` set it on the response.
+    pub fn started_at(&self) -> Option<Instant> {
+        self.started_at
+    }
+
+    /// Consume the stream and fold the deltas into a complete response.
+    pub async fn collect_response(self) -> Result<ChatResponse> {
+        self.collect_response_with(|_| ()).await
+    }
+
+    /// Consume the stream and fold the deltas into a complete response,
+    /// passing each delta to `on_delta` as it arrives, e.g. to show it in a UI.
+    /// 
+    /// The first error of the stream is returned, and the deltas received before it are dropped.
+    pub async fn collect_response_with<F>(mut self, mut on_delta: F) -> Result<ChatResponse>
+    where
+        F: FnMut(&ChatResponseDelta)
+    {
+        let mut aggregator = ChatResponseAggregator::new();
+
+        while let Some(delta) = self.next().await {
+            let delta = delta?;
+            on_delta(&delta);
+            aggregator.push(&delta);
+        }
+
+        let mut response = aggregator.finish();
+        response.metadata.latency = self.started_at.map(|started_at| started_at.elapsed());
+
+        Ok(response)
+    }
+}
+
+impl Stream for ChatResponseStream {
+    type Item = Result<ChatResponseDelta>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>
+    ) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
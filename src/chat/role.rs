@@ -1,10 +1,14 @@
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ChatRole {
     #[serde(rename = "user")]
     User,
 
     #[serde(rename = "assistant")]
     Assistant,
+
+    /// The role of messages with the results of tool calls.
+    #[serde(rename = "tool")]
+    Tool,
 }
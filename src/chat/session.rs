@@ -0,0 +1,477 @@
+use crate::{UnilangError, Result};
+use super::{
+    ChatModel,
+    ChatMessage,
+    ChatResponse,
+    ChatRole,
+};
+
+/// The request that asks the model to summarise the trimmed turns.
+const SUMMARY_PROMPT: &str = "Summarize our conversation so far in a few sentences, keeping the facts needed to continue it.";
+
+/// How a `ChatSession` trims its history when the prompt exceeds the context window.
+///
+/// A turn is a user message with the assistant and tool messages that follow it,
+/// and turns are always trimmed as a whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChatTrimStrategy {
+    /// Drop the oldest turns.
+    #[default]
+    DropOldest,
+
+    /// Keep the first turns and drop the oldest turns after them.
+    KeepFirst(usize),
+
+    /// Replace the oldest turns with a summary written by the model,
+    /// and drop more turns if the summary does not fit either.
+    Summarize,
+}
+
+/// A conversation with a chat model that keeps its own history.
+///
+/// The profile of the model is not part of the history, so it is always kept.
+#[derive(Debug)]
+pub struct ChatSession {
+    model: ChatModel,
+    messages: Vec<ChatMessage>,
+    trim_strategy: ChatTrimStrategy,
+    max_prompt_tokens: u32,
+}
+
+impl ChatSession {
+    /// Create a session that drops the oldest turns to fit the model.
+    pub fn new(model: ChatModel) -> Self {
+        ChatSessionBuilder::new(model).build()
+    }
+
+    /// Create a builder for the session.
+    pub fn builder(model: ChatModel) -> ChatSessionBuilder {
+        ChatSessionBuilder::new(model)
+    }
+
+    pub fn model(&self) -> &ChatModel {
+        &self.model
+    }
+
+    /// Get the history of the session.
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Clear the history of the session.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    /// Send a user message and return the response, which is appended to the history.
+    pub async fn send<S: AsRef<str>>(&mut self, content: S) -> Result<ChatResponse> {
+        self.send_message(ChatMessage::new(ChatRole::User, content)).await
+    }
+
+    /// Send a message, such as the result of a tool call, and return the response.
+    ///
+    /// If the request fails, the history is left as it was before the message,
+    /// even if it has been trimmed, so that the message can be sent again.
+    pub async fn send_message(&mut self, message: ChatMessage) -> Result<ChatResponse> {
+        let history = self.messages.clone();
+        self.messages.push(message);
+
+        let result = match self.trim().await {
+            Ok(()) => self.model.get_complete_chat_response(self.messages.clone()).await,
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(response) => {
+                self.messages.push(response_to_message(&response));
+                Ok(response)
+            },
+            Err(error) => {
+                self.messages = history;
+                Err(error)
+            },
+        }
+    }
+
+    /// Trim the history until the prompt fits in the context window.
+    async fn trim(&mut self) -> Result<()> {
+        if self.fits(&self.messages).await? {
+            return Ok(());
+        }
+
+        // The turns before the first kept one can be trimmed, except for the last turn
+        let starts = turn_starts(&self.messages);
+        let first_trimmable_turn = match self.trim_strategy {
+            ChatTrimStrategy::KeepFirst(turns) => turns,
+            _ => 0,
+        };
+        let trimmable_starts = starts
+            .get(first_trimmable_turn..starts.len().saturating_sub(1))
+            .unwrap_or_default();
+
+        let trim_start = match trimmable_starts.first() {
+            Some(start) => *start,
+            None => return Err(self.overflow_error()),
+        };
+        let trim_ends = trimmable_starts
+            .iter()
+            .skip(1)
+            .chain(starts.last())
+            .copied()
+            .collect::<Vec<usize>>();
+
+        // Leave room for the summary turn if the trimmed turns are summarised
+        let reserved = match self.trim_strategy {
+            ChatTrimStrategy::Summarize => vec![
+                ChatMessage::new(ChatRole::User, SUMMARY_PROMPT),
+                ChatMessage::new(ChatRole::Assistant, ""),
+            ],
+            _ => vec![],
+        };
+
+        // Find the fewest oldest turns to drop
+        let mut trim_end = None;
+        for end in &trim_ends {
+            let messages = [&self.messages[..trim_start], &reserved, &self.messages[*end..]].concat();
+            if self.fits(&messages).await? {
+                trim_end = Some(*end);
+                break;
+            }
+        }
+        let trim_end = match trim_end {
+            Some(end) => end,
+            None => return Err(self.overflow_error()),
+        };
+
+        // Drop more turns if the summary is longer than the room left for it
+        if self.trim_strategy == ChatTrimStrategy::Summarize {
+            let summary = self.summarize(&self.messages[trim_start..trim_end]).await?;
+            for end in trim_ends.iter().filter(|end| **end >= trim_end) {
+                let messages = [&self.messages[..trim_start], &summary, &self.messages[*end..]].concat();
+                if self.fits(&messages).await? {
+                    self.messages = messages;
+                    return Ok(());
+                }
+            }
+        }
+
+        self.messages = [&self.messages[..trim_start], &self.messages[trim_end..]].concat();
+
+        Ok(())
+    }
+
+    /// Ask the model to summarise the messages, and return the summary as a turn.
+    async fn summarize(&self, messages: &[ChatMessage]) -> Result<Vec<ChatMessage>> {
+        let request = ChatMessage::new(ChatRole::User, SUMMARY_PROMPT);
+        let response = self.model
+            .get_complete_chat_response([messages, std::slice::from_ref(&request)].concat())
+            .await?;
+
+        Ok(vec![request, ChatMessage::new(ChatRole::Assistant, response.content)])
+    }
+
+    /// Check if the prompt made of the messages fits in the context window.
+    async fn fits(&self, messages: &[ChatMessage]) -> Result<bool> {
+        Ok(self.model.count_prompt_tokens(messages).await? <= self.max_prompt_tokens)
+    }
+
+    /// Create the error returned when the history cannot be trimmed to fit.
+    fn overflow_error(&self) -> UnilangError {
+        UnilangError::InvalidInput(format!(
+            "the conversation cannot be trimmed to fit in {} tokens",
+            self.max_prompt_tokens,
+        ))
+    }
+}
+
+pub struct ChatSessionBuilder {
+    model: ChatModel,
+    messages: Vec<ChatMessage>,
+    trim_strategy: ChatTrimStrategy,
+    max_prompt_tokens: Option<u32>,
+}
+
+impl ChatSessionBuilder {
+    pub fn new(model: ChatModel) -> Self {
+        Self {
+            model,
+            messages: vec![],
+            trim_strategy: ChatTrimStrategy::DropOldest,
+            max_prompt_tokens: None,
+        }
+    }
+
+    /// Set the history that the session starts with.
+    pub fn messages(mut self, messages: Vec<ChatMessage>) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Set how the history is trimmed to fit the context window.
+    pub fn trim_strategy(mut self, trim_strategy: ChatTrimStrategy) -> Self {
+        self.trim_strategy = trim_strategy;
+        self
+    }
+
+    /// Set the maximum number of tokens in the prompt.
+    /// It defaults to the limit of the model, which leaves no room for the reply of OpenAI's models.
+    pub fn max_prompt_tokens(mut self, max_prompt_tokens: u32) -> Self {
+        self.max_prompt_tokens = Some(max_prompt_tokens);
+        self
+    }
+
+    /// Build the session.
+    pub fn build(self) -> ChatSession {
+        let max_prompt_tokens = self.max_prompt_tokens
+            .unwrap_or_else(|| self.model.name.max_prompt_tokens());
+
+        ChatSession {
+            model: self.model,
+            messages: self.messages,
+            trim_strategy: self.trim_strategy,
+            max_prompt_tokens,
+        }
+    }
+}
+
+/// Get the indices of the messages that start the turns.
+fn turn_starts(messages: &[ChatMessage]) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(index, message)| *index == 0 || message.role == ChatRole::User)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Convert a response to the assistant message that is kept in the history.
+fn response_to_message(response: &ChatResponse) -> ChatMessage {
+    match response.tool_calls.is_empty() {
+        true => ChatMessage::new(ChatRole::Assistant, &response.content),
+        false => ChatMessage {
+            content: response.content.to_owned(),
+            ..ChatMessage::tool_calls(response.tool_calls.to_owned())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    };
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use crate::{
+        UnilangError,
+        chat::{
+            ChatModel,
+            ChatModelName,
+            ChatMessage,
+            ChatProvider,
+            ChatRequestOptions,
+            ChatResponse,
+            ChatResponseMetadata,
+            ChatRole,
+            ChatTokenUsage,
+        },
+    };
+    use super::{
+        ChatSession,
+        ChatTrimStrategy,
+        SUMMARY_PROMPT,
+    };
+
+    /// A provider that replies with the number of the received messages,
+    /// and records the received messages.
+    /// It fails the requests while `failing` is set.
+    #[derive(Debug, Clone, Default)]
+    struct RecordingChatProvider {
+        requests: Arc<Mutex<Vec<Vec<ChatMessage>>>>,
+        failing: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl ChatProvider for RecordingChatProvider {
+        async fn get_complete_chat_response(
+            &self,
+            _model: &ChatModel,
+            messages: Vec<ChatMessage>,
+            _options: &ChatRequestOptions,
+        ) -> crate::Result<ChatResponse> {
+            let content = match messages.last().unwrap().content.as_str() {
+                SUMMARY_PROMPT => "We talked.".to_string(),
+                _ => format!("Received {} messages.", messages.len()),
+            };
+            self.requests.lock().unwrap().push(messages);
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(UnilangError::HttpStatus {
+                    status: 400,
+                    body: "Bad request".to_string(),
+                    retry_after: None,
+                });
+            }
+
+            Ok(ChatResponse {
+                content,
+                is_complete: true,
+                usage: ChatTokenUsage {
+                    prompt_tokens: 0,
+                    completion_tokens: 0,
+                    total_tokens: 0,
+                },
+                tool_calls: vec![],
+                choices: vec![],
+                finish_reason: None,
+                metadata: ChatResponseMetadata::default(),
+            })
+        }
+    }
+
+    /// Create a session whose prompt fits about three turns.
+    fn create_session(provider: &RecordingChatProvider, trim_strategy: ChatTrimStrategy) -> ChatSession {
+        ChatSession::builder(
+            ChatModel::builder()
+                .name(ChatModelName::OpenAIGPT4)
+                .profile("You are a helpful assistant.")
+                .provider(provider.clone())
+                .build()
+        )
+        .trim_strategy(trim_strategy)
+        .max_prompt_tokens(60)
+        .build()
+    }
+
+    fn contents(messages: &[ChatMessage]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_append_responses() -> Result<()> {
+        let provider = RecordingChatProvider::default();
+        let mut session = create_session(&provider, ChatTrimStrategy::DropOldest);
+
+        session.send("Hi").await?;
+        let response = session.send("Hi again").await?;
+
+        assert_eq!(response.content, "Received 3 messages.");
+        assert_eq!(
+            contents(session.messages()),
+            vec!["Hi", "Received 1 messages.", "Hi again", "Received 3 messages."]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest() -> Result<()> {
+        let provider = RecordingChatProvider::default();
+        let mut session = create_session(&provider, ChatTrimStrategy::DropOldest);
+
+        for index in 0..5 {
+            session.send(format!("Message {}", index)).await?;
+        }
+
+        // The latest turns are sent along with the profile
+        assert_eq!(session.messages()[0].content, "Message 2");
+        let requests = provider.requests.lock().unwrap().clone();
+        for request in &requests {
+            assert!(session.model().count_prompt_tokens(request).await? <= 60);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keep_first() -> Result<()> {
+        let provider = RecordingChatProvider::default();
+        let mut session = create_session(&provider, ChatTrimStrategy::KeepFirst(1));
+
+        for index in 0..5 {
+            session.send(format!("Message {}", index)).await?;
+        }
+
+        let messages = session.messages();
+        assert_eq!(messages[0].content, "Message 0");
+        assert_eq!(messages[2].content, "Message 3");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_summarize() -> Result<()> {
+        let provider = RecordingChatProvider::default();
+        let mut session = create_session(&provider, ChatTrimStrategy::Summarize);
+
+        for index in 0..4 {
+            session.send(format!("Message {}", index)).await?;
+        }
+
+        // The trimmed turns are replaced by the summary turn
+        let messages = session.messages();
+        assert_eq!(messages[0].content, SUMMARY_PROMPT);
+        assert_eq!(messages[1].content, "We talked.");
+        assert_eq!(messages[1].role, ChatRole::Assistant);
+        assert_eq!(
+            contents(&messages[2..]),
+            vec!["Message 3", "Received 3 messages."]
+        );
+
+        // The summary is written from the trimmed turns
+        let requests = provider.requests.lock().unwrap().clone();
+        assert_eq!(
+            contents(&requests[3]),
+            vec!["Message 0", "Received 1 messages.", "Message 1", "Received 3 messages.", "Message 2", "Received 5 messages.", SUMMARY_PROMPT]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_message_too_long() -> Result<()> {
+        let provider = RecordingChatProvider::default();
+        let mut session = create_session(&provider, ChatTrimStrategy::DropOldest);
+
+        session.send("Hi").await?;
+        match session.send("Rust ".repeat(100)).await {
+            Err(UnilangError::InvalidInput(_)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The failed message is not kept
+        assert_eq!(session.messages().len(), 2);
+        assert_eq!(provider.requests.lock().unwrap().len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keep_history_after_failure() -> Result<()> {
+        let provider = RecordingChatProvider::default();
+        let mut session = create_session(&provider, ChatTrimStrategy::DropOldest);
+
+        for index in 0..3 {
+            session.send(format!("Message {}", index)).await?;
+        }
+        let history = contents(session.messages())
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        assert_eq!(history[0], "Message 0");
+
+        provider.failing.store(true, Ordering::SeqCst);
+        assert!(session.send("Message 3").await.is_err());
+
+        // The history was trimmed for the failed request, but is restored after it
+        let requests = provider.requests.lock().unwrap().clone();
+        assert_ne!(requests.last().unwrap()[0].content, "Message 0");
+        assert_eq!(contents(session.messages()), history);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+/// A tool that the model may call, which works with every provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatTool {
+    pub name: String,
+    pub description: String,
+
+    /// Parameters of the tool described as a JSON Schema object.
+    pub parameters: Value,
+}
+
+impl ChatTool {
+    pub fn new<S: AsRef<str>, T: AsRef<str>>(name: S, description: T, parameters: Value) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            description: description.as_ref().to_string(),
+            parameters,
+        }
+    }
+}
+
+/// A tool call requested by the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatToolCall {
+    /// ID of the call, which is used to match the result with the call.
+    pub id: String,
+
+    pub name: String,
+
+    /// Arguments generated by the model in JSON, which may not be valid.
+    pub arguments: String,
+}
+
+/// A piece of a tool call in a streamed response.
+///
+/// Pieces with the same index in a choice belong to the same tool call.
+/// The ID and name come with the first piece, while the arguments may be split across the pieces.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChatToolCallDelta {
+    /// Index of the tool call in the choice.
+    pub index: u32,
+
+    pub id: Option<String>,
+    pub name: Option<String>,
+
+    /// Piece of the arguments in JSON.
+    pub arguments: String,
+}
+
+impl ChatToolCallDelta {
+    /// Create the only piece of a tool call that is not split.
+    pub fn whole(index: u32, tool_call: ChatToolCall) -> Self {
+        Self {
+            index,
+            id: Some(tool_call.id),
+            name: Some(tool_call.name),
+            arguments: tool_call.arguments,
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    fmt::Debug,
+    path::PathBuf,
+};
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use tracing::info;
+use crate::{
+    UnilangError,
+    Result,
+};
+
+lazy_static! {
+    static ref DOTENV_FILEPATH: Option<PathBuf> = {
+        info!("Loading dotenv");
+        dotenv::dotenv().ok()
+    };
+}
+
+/// API keys of the providers.
+/// 
+/// Keys that are not needed by the called provider can be left unset.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    openai_api_key: Option<String>,
+    qianfan_access_key: Option<String>,
+    qianfan_secret_key: Option<String>,
+}
+
+impl Credentials {
+    /// Create a builder for the credentials.
+    pub fn builder() -> CredentialsBuilder {
+        CredentialsBuilder::new()
+    }
+
+    /// Get the API key of OpenAI.
+    pub fn openai_api_key(&self) -> Result<&str> {
+        require(&self.openai_api_key, "OPENAI_API_KEY")
+    }
+
+    /// Get the access key of Qianfan.
+    pub fn qianfan_access_key(&self) -> Result<&str> {
+        require(&self.qianfan_access_key, "QIANFAN_ACCESS_KEY")
+    }
+
+    /// Get the secret key of Qianfan.
+    pub fn qianfan_secret_key(&self) -> Result<&str> {
+        require(&self.qianfan_secret_key, "QIANFAN_SECRET_KEY")
+    }
+
+    /// Read the credentials from variables with the same names as in `.env.template`.
+    fn from_vars<I: IntoIterator<Item = (String, String)>>(vars: I) -> Self {
+        let mut credentials = Self::default();
+
+        for (name, value) in vars {
+            match name.as_str() {
+                "OPENAI_API_KEY" => credentials.openai_api_key = Some(value),
+                "QIANFAN_ACCESS_KEY" => credentials.qianfan_access_key = Some(value),
+                "QIANFAN_SECRET_KEY" => credentials.qianfan_secret_key = Some(value),
+                _ => {},
+            }
+        }
+
+        credentials
+    }
+}
+
+/// Get the key if it is set and not empty.
+fn require<'a>(key: &'a Option<String>, name: &str) -> Result<&'a str> {
+    match key.as_deref() {
+        Some(key) if !key.is_empty() => Ok(key),
+        _ => Err(UnilangError::Auth(format!("{} is missing", name))),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CredentialsBuilder {
+    credentials: Credentials,
+}
+
+impl CredentialsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the API key of OpenAI.
+    pub fn openai_api_key<S: AsRef<str>>(mut self, openai_api_key: S) -> Self {
+        self.credentials.openai_api_key = Some(openai_api_key.as_ref().to_string());
+        self
+    }
+
+    /// Set the access key of Qianfan.
+    pub fn qianfan_access_key<S: AsRef<str>>(mut self, qianfan_access_key: S) -> Self {
+        self.credentials.qianfan_access_key = Some(qianfan_access_key.as_ref().to_string());
+        self
+    }
+
+    /// Set the secret key of Qianfan.
+    pub fn qianfan_secret_key<S: AsRef<str>>(mut self, qianfan_secret_key: S) -> Self {
+        self.credentials.qianfan_secret_key = Some(qianfan_secret_key.as_ref().to_string());
+        self
+    }
+
+    /// Build the credentials.
+    pub fn build(self) -> Credentials {
+        self.credentials
+    }
+}
+
+/// A source of credentials.
+/// 
+/// The credentials are fetched for every request,
+/// so a provider can rotate keys without rebuilding the models.
+#[async_trait]
+pub trait CredentialProvider: Debug + Send + Sync {
+    async fn get_credentials(&self) -> Result<Credentials>;
+}
+
+/// Read the credentials from environment variables.
+/// 
+/// The `.env` file in the working directory, if any, is loaded first.
+#[derive(Debug, Default)]
+pub struct EnvCredentialProvider;
+
+#[async_trait]
+impl CredentialProvider for EnvCredentialProvider {
+    async fn get_credentials(&self) -> Result<Credentials> {
+        let _ = DOTENV_FILEPATH.as_ref();
+
+        Ok(Credentials::from_vars(std::env::vars()))
+    }
+}
+
+/// Provide the same credentials for every request.
+#[derive(Debug)]
+pub struct StaticCredentialProvider {
+    credentials: Credentials,
+}
+
+impl StaticCredentialProvider {
+    pub fn new(credentials: Credentials) -> Self {
+        Self { credentials }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredentialProvider {
+    async fn get_credentials(&self) -> Result<Credentials> {
+        Ok(self.credentials.clone())
+    }
+}
+
+/// Read the credentials from a file in the format of `.env.template`.
+/// 
+/// The file is read for every request, so changes to it take effect immediately.
+#[derive(Debug)]
+pub struct FileCredentialProvider {
+    path: PathBuf,
+}
+
+impl FileCredentialProvider {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for FileCredentialProvider {
+    async fn get_credentials(&self) -> Result<Credentials> {
+        let to_auth_error = |error: dotenv::Error| UnilangError::Auth(
+            format!("Failed to read credentials from {}: {}", self.path.display(), error)
+        );
+
+        // Unlike its replacement, `from_path_iter` does not modify the environment variables
+        #[allow(deprecated)]
+        let vars = dotenv::from_path_iter(&self.path);
+
+        Ok(Credentials::from_vars(
+            vars
+                .map_err(to_auth_error)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(to_auth_error)?
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use crate::UnilangError;
+    use super::{
+        Credentials,
+        CredentialProvider,
+        FileCredentialProvider,
+        StaticCredentialProvider,
+    };
+
+    #[tokio::test]
+    async fn test_static_credential_provider() -> Result<()> {
+        let provider = StaticCredentialProvider::new(
+            Credentials::builder()
+                .openai_api_key("sk-test")
+                .build()
+        );
+        let credentials = provider.get_credentials().await?;
+
+        assert_eq!(credentials.openai_api_key()?, "sk-test");
+        assert!(matches!(credentials.qianfan_access_key(), Err(UnilangError::Auth(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_credential_provider() -> Result<()> {
+        let path = std::env::temp_dir().join("unilang-test-credentials.env");
+        std::fs::write(&path, "# Qianfan\nQIANFAN_ACCESS_KEY = \"ak-test\"\nQIANFAN_SECRET_KEY = \"\"\n")?;
+
+        let credentials = FileCredentialProvider::new(&path).get_credentials().await?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(credentials.qianfan_access_key()?, "ak-test");
+
+        // Empty keys are treated as missing
+        assert!(matches!(credentials.qianfan_secret_key(), Err(UnilangError::Auth(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_file() {
+        let provider = FileCredentialProvider::new("/nonexistent/unilang.env");
+
+        assert!(matches!(provider.get_credentials().await, Err(UnilangError::Auth(_))));
+    }
+}
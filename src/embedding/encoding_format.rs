@@ -0,0 +1,10 @@
+/// Format in which the provider transfers the embeddings.
+/// 
+/// The embeddings are always returned as vectors of `f32`,
+/// so the format only affects the size of the responses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmbeddingEncodingFormat {
+    #[default]
+    Float,
+    Base64,
+}
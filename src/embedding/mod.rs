@@ -1 +1,24 @@
+mod model;
+pub use model::{EmbeddingModel, EmbeddingModelBuilder};
 
+mod model_names;
+pub use model_names::EmbeddingModelName;
+
+mod encoding_format;
+pub use encoding_format::EmbeddingEncodingFormat;
+
+mod response;
+pub use response::{
+    EmbeddingResponse,
+    Embedding,
+    EmbeddingTokenUsage,
+};
+
+mod provider;
+pub use provider::EmbeddingProvider;
+
+mod openai;
+pub use openai::OpenAIEmbeddingProvider;
+
+mod qianfan;
+pub use qianfan::QianfanEmbeddingProvider;
@@ -0,0 +1,233 @@
+use std::time::Duration;
+use reqwest::Client;
+use crate::{
+    Result,
+    CredentialProvider,
+    EnvCredentialProvider,
+};
+use super::{
+    EmbeddingModelName,
+    EmbeddingEncodingFormat,
+    EmbeddingProvider,
+    EmbeddingResponse,
+    OpenAIEmbeddingProvider,
+    QianfanEmbeddingProvider,
+};
+
+#[derive(Debug)]
+pub struct EmbeddingModel {
+    pub client: Client,
+
+    /// Base URL of the provider's API, which is only set to call it through a proxy.
+    pub api_base: Option<String>,
+
+    pub name: EmbeddingModelName,
+
+    /// Number of dimensions of the embeddings, if the model supports shortening them.
+    pub dimensions: Option<u32>,
+
+    /// Format in which the embeddings are transferred, which only applies to OpenAI.
+    pub encoding_format: EmbeddingEncodingFormat,
+    pub provider: Box<dyn EmbeddingProvider>,
+    pub credential_provider: Box<dyn CredentialProvider>,
+}
+
+impl EmbeddingModel {
+    pub fn new(
+        client: Client,
+        api_base: Option<String>,
+        name: EmbeddingModelName,
+        dimensions: Option<u32>,
+        encoding_format: EmbeddingEncodingFormat,
+        provider: Box<dyn EmbeddingProvider>,
+        credential_provider: Box<dyn CredentialProvider>,
+    ) -> Self {
+        Self {
+            client,
+            api_base,
+            name,
+            dimensions,
+            encoding_format,
+            provider,
+            credential_provider,
+        }
+    }
+
+    /// Create a builder for the embedding model.
+    pub fn builder() -> EmbeddingModelBuilder {
+        EmbeddingModelBuilder::new()
+    }
+
+    /// Get the embedding of a single input.
+    pub async fn get_embedding<S: AsRef<str>>(&self, input: S) -> Result<EmbeddingResponse> {
+        self.provider.get_embeddings(self, vec![input.as_ref().to_string()]).await
+    }
+
+    /// Get the embeddings of a batch of inputs.
+    pub async fn get_embeddings(&self, inputs: Vec<String>) -> Result<EmbeddingResponse> {
+        self.provider.get_embeddings(self, inputs).await
+    }
+}
+
+pub struct EmbeddingModelBuilder {
+    client: Client,
+    api_base: Option<String>,
+    name: EmbeddingModelName,
+    dimensions: Option<u32>,
+    encoding_format: EmbeddingEncodingFormat,
+    provider: Option<Box<dyn EmbeddingProvider>>,
+    credential_provider: Box<dyn CredentialProvider>,
+}
+
+impl Default for EmbeddingModelBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmbeddingModelBuilder {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap(),
+            api_base: None,
+            name: EmbeddingModelName::OpenAITextEmbedding3Small,
+            dimensions: None,
+            encoding_format: EmbeddingEncodingFormat::Float,
+            provider: None,
+            credential_provider: Box::new(EnvCredentialProvider),
+        }
+    }
+
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set the base URL of the provider's API, e.g. to call it through a proxy.
+    /// The official API of the provider is called by default.
+    pub fn api_base<S: AsRef<str>>(mut self, api_base: S) -> Self {
+        self.api_base = Some(api_base.as_ref().trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Set the name of the embedding model.
+    pub fn name(mut self, name: EmbeddingModelName) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Set the number of dimensions of the embeddings.
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Set the format in which the embeddings are transferred.
+    pub fn encoding_format(mut self, encoding_format: EmbeddingEncodingFormat) -> Self {
+        self.encoding_format = encoding_format;
+        self
+    }
+
+    /// Set the provider that serves the embedding requests.
+    /// If it is not set, the provider is chosen according to the model name.
+    pub fn provider<P: EmbeddingProvider + 'static>(mut self, provider: P) -> Self {
+        self.provider = Some(Box::new(provider));
+        self
+    }
+
+    /// Set the source of the API keys.
+    /// The keys are read from environment variables by default.
+    pub fn credential_provider<P: CredentialProvider + 'static>(mut self, credential_provider: P) -> Self {
+        self.credential_provider = Box::new(credential_provider);
+        self
+    }
+
+    /// Build the embedding model.
+    pub fn build(self) -> EmbeddingModel {
+        // Choose the built-in provider if no provider is set
+        let provider = match self.provider {
+            Some(provider) => provider,
+            None => default_provider(&self.name),
+        };
+
+        EmbeddingModel::new(
+            self.client,
+            self.api_base,
+            self.name,
+            self.dimensions,
+            self.encoding_format,
+            provider,
+            self.credential_provider,
+        )
+    }
+}
+
+/// Get the built-in provider of the given model.
+fn default_provider(model_name: &EmbeddingModelName) -> Box<dyn EmbeddingProvider> {
+    match model_name {
+        EmbeddingModelName::OpenAITextEmbeddingAda002
+        | EmbeddingModelName::OpenAITextEmbedding3Small
+        | EmbeddingModelName::OpenAITextEmbedding3Large => Box::new(OpenAIEmbeddingProvider),
+        EmbeddingModelName::QianfanEmbeddingV1
+        | EmbeddingModelName::QianfanBgeLargeZh
+        | EmbeddingModelName::QianfanBgeLargeEn
+        | EmbeddingModelName::QianfanTao8K => Box::new(QianfanEmbeddingProvider),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use crate::embedding::{
+        EmbeddingModel,
+        EmbeddingProvider,
+        EmbeddingResponse,
+        Embedding,
+        EmbeddingTokenUsage,
+    };
+
+    /// A provider that embeds each input as its length.
+    #[derive(Debug)]
+    struct LengthEmbeddingProvider;
+
+    #[async_trait]
+    impl EmbeddingProvider for LengthEmbeddingProvider {
+        async fn get_embeddings(
+            &self,
+            _model: &EmbeddingModel,
+            inputs: Vec<String>,
+        ) -> crate::Result<EmbeddingResponse> {
+            Ok(EmbeddingResponse {
+                embeddings: inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, input)| Embedding {
+                        index,
+                        vector: vec![input.len() as f32],
+                    })
+                    .collect(),
+                usage: EmbeddingTokenUsage::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_provider() -> Result<()> {
+        let model = EmbeddingModel::builder()
+            .provider(LengthEmbeddingProvider)
+            .build();
+
+        let response = model.get_embedding("Rust").await?;
+        assert_eq!(response.embeddings[0].vector, vec![4.0]);
+
+        let response = model.get_embeddings(vec!["Rust".to_string(), "Go".to_string()]).await?;
+        assert_eq!(response.embeddings.len(), 2);
+        assert_eq!(response.embeddings[1].vector, vec![2.0]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingModelName {
+    OpenAITextEmbeddingAda002,
+    OpenAITextEmbedding3Small,
+    OpenAITextEmbedding3Large,
+    QianfanEmbeddingV1,
+    QianfanBgeLargeZh,
+    QianfanBgeLargeEn,
+    QianfanTao8K,
+}
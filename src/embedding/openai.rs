@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use crate::{
+    UnilangError,
+    Result,
+    embedding::{
+        EmbeddingModel,
+        EmbeddingModelName,
+        EmbeddingEncodingFormat,
+        EmbeddingProvider,
+        EmbeddingResponse,
+        Embedding,
+        EmbeddingTokenUsage,
+    },
+    openai::{
+        self,
+        embedding::{
+            OpenAIEmbeddingRequestBody,
+            OpenAIEmbeddingResponse,
+            OpenAIEmbeddingEncodingFormat,
+        },
+    },
+};
+
+/// The provider that serves embedding requests with OpenAI's embedding API.
+#[derive(Debug, Default)]
+pub struct OpenAIEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbeddingProvider {
+    async fn get_embeddings(
+        &self,
+        model: &EmbeddingModel,
+        inputs: Vec<String>,
+    ) -> Result<EmbeddingResponse> {
+        // Call API to get embeddings
+        Ok(
+            openai::embedding::get_embeddings(
+                &model.client,
+                model.api_base.as_deref().unwrap_or(openai::OPENAI_API_BASE),
+                &model.credential_provider.get_credentials().await?,
+                &create_request_body(model, inputs)?
+            ).await?
+            .into()
+        )
+    }
+}
+
+/// Create the request body of OpenAI's embedding API.
+fn create_request_body(model: &EmbeddingModel, inputs: Vec<String>) -> Result<OpenAIEmbeddingRequestBody> {
+    let builder = OpenAIEmbeddingRequestBody::builder()
+        .model(embedding_model_name_to_string(&model.name)?)
+        .inputs(inputs)
+        .encoding_format(match model.encoding_format {
+            EmbeddingEncodingFormat::Float => OpenAIEmbeddingEncodingFormat::Float,
+            EmbeddingEncodingFormat::Base64 => OpenAIEmbeddingEncodingFormat::Base64,
+        });
+
+    match model.dimensions {
+        Some(dimensions) => Ok(builder.dimensions(dimensions).build()),
+        None => Ok(builder.build()),
+    }
+}
+
+/// Convert an `EmbeddingModelName` to a model name of OpenAI's API.
+fn embedding_model_name_to_string(model_name: &EmbeddingModelName) -> Result<&'static str> {
+    match model_name {
+        EmbeddingModelName::OpenAITextEmbeddingAda002 => Ok("text-embedding-ada-002"),
+        EmbeddingModelName::OpenAITextEmbedding3Small => Ok("text-embedding-3-small"),
+        EmbeddingModelName::OpenAITextEmbedding3Large => Ok("text-embedding-3-large"),
+        _ => Err(UnilangError::UnsupportedModel(
+            format!("{:?} is not available in OpenAI's embedding models", model_name)
+        )),
+    }
+}
+
+impl From<OpenAIEmbeddingResponse> for EmbeddingResponse {
+    fn from(response: OpenAIEmbeddingResponse) -> Self {
+        let mut embeddings = response.data
+            .into_iter()
+            .map(|embedding| Embedding {
+                index: embedding.index,
+                vector: embedding.embedding,
+            })
+            .collect::<Vec<Embedding>>();
+
+        // The embeddings are not guaranteed to be in the order of the inputs
+        embeddings.sort_by_key(|embedding| embedding.index);
+
+        Self {
+            embeddings,
+            usage: EmbeddingTokenUsage {
+                prompt_tokens: response.usage.prompt_tokens,
+                total_tokens: response.usage.total_tokens,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        StaticCredentialProvider,
+        embedding::{
+            EmbeddingModel,
+            EmbeddingModelName,
+            EmbeddingEncodingFormat,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_get_embeddings_with_mock_server() -> Result<()> {
+        let encode = |vector: &[f32]| STANDARD.encode(
+            vector
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect::<Vec<u8>>()
+        );
+        let server = MockServer::start().await;
+        Mock::given(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    {"object": "embedding", "index": 1, "embedding": encode(&[0.25, 1.0])},
+                    {"object": "embedding", "index": 0, "embedding": encode(&[0.5, -0.25])},
+                ],
+                "model": "text-embedding-3-large",
+                "usage": {"prompt_tokens": 8, "total_tokens": 8},
+            })))
+            .mount(&server)
+            .await;
+        let model = EmbeddingModel::builder()
+            .name(EmbeddingModelName::OpenAITextEmbedding3Large)
+            .dimensions(2)
+            .encoding_format(EmbeddingEncodingFormat::Base64)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .openai_api_key("sk-test")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .build();
+
+        let response = model.get_embeddings(vec!["What is Rust?".to_string(), "What is Go?".to_string()]).await?;
+
+        // The embeddings are sorted by the index of the inputs
+        assert_eq!(response.embeddings[0].index, 0);
+        assert_eq!(response.embeddings[0].vector, vec![0.5, -0.25]);
+        assert_eq!(response.embeddings[1].vector, vec![0.25, 1.0]);
+        assert_eq!(response.usage.total_tokens, 8);
+
+        assert_eq!(
+            server.received_requests().await.unwrap()[0].body_json::<serde_json::Value>()?,
+            json!({
+                "model": "text-embedding-3-large",
+                "input": ["What is Rust?", "What is Go?"],
+                "encoding_format": "base64",
+                "dimensions": 2,
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use std::fmt::Debug;
+use async_trait::async_trait;
+use crate::Result;
+use super::{
+    EmbeddingModel,
+    EmbeddingResponse,
+};
+
+/// A backend that serves the embedding requests of an `EmbeddingModel`.
+/// 
+/// OpenAI and Qianfan are supported out of the box.
+/// Implement this trait to plug in other backends.
+#[async_trait]
+pub trait EmbeddingProvider: Debug + Send + Sync {
+    /// Call the backend and return the embeddings of the inputs in order.
+    async fn get_embeddings(
+        &self,
+        model: &EmbeddingModel,
+        inputs: Vec<String>,
+    ) -> Result<EmbeddingResponse>;
+}
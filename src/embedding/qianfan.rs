@@ -0,0 +1,207 @@
+use async_trait::async_trait;
+use crate::{
+    UnilangError,
+    Result,
+    embedding::{
+        EmbeddingModel,
+        EmbeddingModelName,
+        EmbeddingProvider,
+        EmbeddingResponse,
+        Embedding,
+        EmbeddingTokenUsage,
+    },
+    qianfan::{
+        self,
+        embedding::{
+            QianfanEmbeddingModelName,
+            QianfanEmbeddingRequestBody,
+        },
+    },
+    tokenizer::QianfanTokenEstimator,
+};
+
+/// The provider that serves embedding requests with Qianfan's embedding API.
+/// 
+/// The inputs are split into as many requests as the limits of the model require.
+/// The tokens of the inputs are estimated with `QianfanTokenEstimator`,
+/// so an input close to the token limit of the model may be rejected by Qianfan anyway.
+#[derive(Debug, Default)]
+pub struct QianfanEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for QianfanEmbeddingProvider {
+    async fn get_embeddings(
+        &self,
+        model: &EmbeddingModel,
+        inputs: Vec<String>,
+    ) -> Result<EmbeddingResponse> {
+        // Get the model name
+        let model_name = embedding_model_name_to_qianfan_model_name(&model.name)?;
+
+        // Reject the inputs that are too long before sending any request
+        if let Some((index, _)) = inputs
+            .iter()
+            .enumerate()
+            .find(|(_, input)| input.chars().count() > model_name.max_input_chars())
+        {
+            return Err(UnilangError::InvalidInput(format!(
+                "input {} exceeds the limit of {} characters of {:?}",
+                index,
+                model_name.max_input_chars(),
+                model.name,
+            )));
+        }
+        if let Some((index, _)) = inputs
+            .iter()
+            .enumerate()
+            .find(|(_, input)| QianfanTokenEstimator.estimate_tokens(input) > model_name.max_input_tokens())
+        {
+            return Err(UnilangError::InvalidInput(format!(
+                "input {} exceeds the limit of {} tokens of {:?}",
+                index,
+                model_name.max_input_tokens(),
+                model.name,
+            )));
+        }
+
+        let api_base = model.api_base.as_deref().unwrap_or(qianfan::QIANFAN_API_BASE);
+        let credentials = model.credential_provider.get_credentials().await?;
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        let mut usage = EmbeddingTokenUsage::default();
+
+        // Call API batch by batch
+        for (batch_index, batch) in inputs.chunks(model_name.max_inputs()).enumerate() {
+            let response = qianfan::embedding::get_embeddings(
+                &model.client,
+                api_base,
+                &credentials,
+                model_name,
+                &QianfanEmbeddingRequestBody::new(batch.to_vec()),
+            ).await?;
+
+            // Offset the indices of the batch to the indices of the inputs
+            let offset = batch_index * model_name.max_inputs();
+            embeddings.extend(
+                response.data
+                    .into_iter()
+                    .map(|embedding| Embedding {
+                        index: offset + embedding.index,
+                        vector: embedding.embedding,
+                    })
+            );
+            usage.prompt_tokens += response.usage.prompt_tokens;
+            usage.total_tokens += response.usage.total_tokens;
+        }
+
+        embeddings.sort_by_key(|embedding| embedding.index);
+
+        Ok(EmbeddingResponse { embeddings, usage })
+    }
+}
+
+/// Convert an `EmbeddingModelName` to a `QianfanEmbeddingModelName`.
+fn embedding_model_name_to_qianfan_model_name(model_name: &EmbeddingModelName) -> Result<QianfanEmbeddingModelName> {
+    match model_name {
+        EmbeddingModelName::QianfanEmbeddingV1 => Ok(QianfanEmbeddingModelName::EmbeddingV1),
+        EmbeddingModelName::QianfanBgeLargeZh => Ok(QianfanEmbeddingModelName::BgeLargeZh),
+        EmbeddingModelName::QianfanBgeLargeEn => Ok(QianfanEmbeddingModelName::BgeLargeEn),
+        EmbeddingModelName::QianfanTao8K => Ok(QianfanEmbeddingModelName::Tao8K),
+        _ => Err(UnilangError::UnsupportedModel(
+            format!("{:?} is not available in Qianfan's embedding models", model_name)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        StaticCredentialProvider,
+        UnilangError,
+        embedding::{
+            EmbeddingModel,
+            EmbeddingModelName,
+        },
+        qianfan::mock_access_token,
+    };
+
+    fn create_embedding_response(indices: std::ops::Range<usize>) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "id": "as-1",
+            "object": "embedding_list",
+            "created": 1700000000,
+            "data": indices
+                .map(|index| json!({"object": "embedding", "embedding": [index as f32], "index": index}))
+                .collect::<Vec<_>>(),
+            "usage": {"prompt_tokens": 2, "total_tokens": 2},
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_split_inputs_into_batches() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-embedding-batches").await;
+        for response in [create_embedding_response(0..16), create_embedding_response(0..4)] {
+            Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/embeddings/embedding-v1"))
+                .respond_with(response)
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let model = EmbeddingModel::builder()
+            .name(EmbeddingModelName::QianfanEmbeddingV1)
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .qianfan_access_key("ak-embedding-batches")
+                    .qianfan_secret_key("sk-embedding-batches")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .build();
+
+        let inputs = (0..20).map(|index| format!("文本 {}", index)).collect::<Vec<String>>();
+        let response = model.get_embeddings(inputs.clone()).await?;
+
+        // The embeddings of the second batch follow those of the first one
+        assert_eq!(response.embeddings.len(), 20);
+        assert_eq!(response.embeddings[17].index, 17);
+        assert_eq!(response.embeddings[17].vector, vec![1.0]);
+        assert_eq!(response.usage.total_tokens, 4);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].body_json::<serde_json::Value>()?["input"], json!(inputs[..16]));
+        assert_eq!(requests[2].body_json::<serde_json::Value>()?["input"], json!(inputs[16..]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reject_long_input() -> Result<()> {
+        let model = EmbeddingModel::builder()
+            .name(EmbeddingModelName::QianfanBgeLargeZh)
+            .credential_provider(StaticCredentialProvider::new(Credentials::builder().build()))
+            .build();
+
+        match model.get_embedding("长".repeat(2001)).await {
+            Err(UnilangError::InvalidInput(_)) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // 600 Chinese characters are within the characters but not the tokens of the model
+        match model.get_embedding("长".repeat(600)).await {
+            Err(UnilangError::InvalidInput(message)) => assert!(message.contains("512 tokens")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        Ok(())
+    }
+}
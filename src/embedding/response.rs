@@ -0,0 +1,19 @@
+#[derive(Debug, Clone)]
+pub struct EmbeddingResponse {
+    /// Embeddings in the same order as the inputs.
+    pub embeddings: Vec<Embedding>,
+    pub usage: EmbeddingTokenUsage,
+}
+
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    /// Index of the input that the embedding belongs to.
+    pub index: usize,
+    pub vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingTokenUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
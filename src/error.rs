@@ -0,0 +1,77 @@
+use std::time::Duration;
+use thiserror::Error;
+use crate::{
+    openai::OpenAIError,
+    qianfan::QianfanError,
+};
+
+/// Errors that can occur when calling the LLM APIs.
+#[derive(Debug, Error)]
+pub enum UnilangError {
+    /// Failed to send the request or to receive the response.
+    #[error("Transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// The server responded with an unsuccessful HTTP status
+    /// and a body that is not a known provider error.
+    #[error("HTTP status {status}: {body}")]
+    HttpStatus {
+        status: u16,
+        body: String,
+
+        /// Delay that the server asks for before the request is sent again.
+        retry_after: Option<Duration>,
+    },
+
+    /// OpenAI's API responded with an error.
+    #[error(transparent)]
+    OpenAI(#[from] OpenAIError),
+
+    /// Qianfan's API responded with an error.
+    #[error(transparent)]
+    Qianfan(#[from] QianfanError),
+
+    /// Failed to authenticate with the provider.
+    #[error("Authentication error: {0}")]
+    Auth(String),
+
+    /// Failed to serialize the request or to parse the response.
+    #[error("Parse error: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    /// The model is not supported by the provider.
+    #[error("Unsupported model: {0}")]
+    UnsupportedModel(String),
+
+    /// The input exceeds the limits of the model and is not sent.
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    /// The stream ended before the provider finished the response.
+    #[error("Incomplete stream: {0}")]
+    IncompleteStream(String),
+}
+
+impl UnilangError {
+    /// Check whether the request may succeed if it is sent again later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            UnilangError::Transport(error) => error.is_timeout() || error.is_connect(),
+            UnilangError::HttpStatus { status, .. } => *status == 429 || *status >= 500,
+            UnilangError::OpenAI(error) => error.is_retryable(),
+            UnilangError::Qianfan(error) => error.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Get the delay that the server asks for before the request is sent again.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            UnilangError::HttpStatus { retry_after, .. } => *retry_after,
+            UnilangError::OpenAI(error) => error.retry_after,
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T, E = UnilangError> = std::result::Result<T, E>;
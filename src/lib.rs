@@ -2,14 +2,26 @@ pub mod chat;
 pub mod embedding;
 pub mod openai;
 pub mod qianfan;
+pub mod tokenizer;
 
-use std::path::PathBuf;
-use lazy_static::lazy_static;
-use tracing::info;
+mod error;
+pub use error::{UnilangError, Result};
 
-lazy_static! {
-    static ref DOTENV_FILEPATH: Option<PathBuf> = {
-        info!("Loading dotenv");
-        dotenv::dotenv().ok()
-    };
-}
+mod credentials;
+pub use credentials::{
+    Credentials,
+    CredentialsBuilder,
+    CredentialProvider,
+    EnvCredentialProvider,
+    StaticCredentialProvider,
+    FileCredentialProvider,
+};
+
+mod rate_limit;
+pub use rate_limit::{RateLimiter, RateLimiterBuilder, RateLimitPermit};
+
+mod retry;
+pub use retry::{RetryPolicy, RetryPolicyBuilder};
+
+mod sse;
+pub use sse::{SseDecoder, SseEvent, SseStream};
@@ -1,8 +1,8 @@
-use anyhow::{Result, anyhow};
 use futures::Stream;
 use reqwest::Client;
+use crate::{Credentials, Result};
 use super::{
-    super::OPENAI_API_KEY,
+    super::error::parse_error_response,
     OpenAIChatRequestBody,
     OpenAIChatCompletion,
     OpenAIChatCompletionChunk,
@@ -10,8 +10,12 @@ use super::{
 };
 
 /// Call OpenAI chat API and return a complete chat response.
+/// 
+/// The API is called at `api_base`, which is `OPENAI_API_BASE` unless a proxy is used.
 pub async fn get_complete_chat_response(
     client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
     request_body: &OpenAIChatRequestBody
 ) -> Result<OpenAIChatCompletion> {
     // Convert to a map
@@ -26,29 +30,33 @@ pub async fn get_complete_chat_response(
 
     // Call API to get chat response
     let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", OPENAI_API_KEY.as_str()))
+        .post(format!("{}/chat/completions", api_base))
+        .bearer_auth(credentials.openai_api_key()?)
         .json(&request_body)
         .send()
         .await?;
 
-    // Get the response content
+    // Get the response status, headers and content
+    let status = response.status();
+    let headers = response.headers().clone();
     let response_content = response.text().await?;
 
     // Parse the response content
-    // If the response is successful, parse the response content as OpenAIChatResponse
+    // If the response is successful, parse the response content as OpenAIChatCompletion
     // If the response is not successful, parse the response content as OpenAIError
-    if let Ok(response) = serde_json::from_str::<OpenAIChatCompletion>(&response_content) {
-        Ok(response)
+    if status.is_success() {
+        Ok(serde_json::from_str::<OpenAIChatCompletion>(&response_content)?)
     } else {
-        Err(anyhow!(response_content))
+        Err(parse_error_response(status.as_u16(), &headers, response_content))
     }
 }
 
 pub async fn get_streamed_chat_response(
     client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
     request_body: &OpenAIChatRequestBody,
-) -> Result<impl Stream<Item = OpenAIChatCompletionChunk>> {
+) -> Result<impl Stream<Item = Result<OpenAIChatCompletionChunk>>> {
     // Convert to a map
     let mut request_body = serde_json::to_value(request_body)?.as_object()
         .unwrap()
@@ -61,12 +69,20 @@ pub async fn get_streamed_chat_response(
 
     // Call API to get chat response
     let response = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", OPENAI_API_KEY.as_str()))
+        .post(format!("{}/chat/completions", api_base))
+        .bearer_auth(credentials.openai_api_key()?)
         .json(&request_body)
         .send()
         .await?;
 
+    // OpenAI responds with an error body instead of the event stream
+    // if the request is rejected
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        return Err(parse_error_response(status.as_u16(), &headers, response.text().await?));
+    }
+
     // Create ChatResponseStream from the response bytes stream
     Ok(
         OpenAIChatCompletionStream::new(response.bytes_stream())
@@ -80,10 +96,15 @@ mod tests {
     use anyhow::Result;
     use futures::StreamExt;
     use reqwest::Client;
-    use crate::openai::chat::{
-        OpenAIChatMessage,
-        OpenAIChatRole,
-        OpenAIChatRequestBody,
+    use crate::{
+        CredentialProvider,
+        EnvCredentialProvider,
+        openai::OPENAI_API_BASE,
+        openai::chat::{
+            OpenAIChatMessage,
+            OpenAIChatRole,
+            OpenAIChatRequestBody,
+        },
     };
     use super::{
         get_complete_chat_response,
@@ -97,12 +118,11 @@ mod tests {
             &Client::builder()
                 .timeout(Duration::from_secs(60))
                 .build()?,
+            OPENAI_API_BASE,
+            &EnvCredentialProvider.get_credentials().await?,
             &OpenAIChatRequestBody::builder()
                 .messages(vec![
-                    OpenAIChatMessage{
-                        role: OpenAIChatRole::User,
-                        content: "What is Rust?".to_string(),
-                    },
+                    OpenAIChatMessage::new(OpenAIChatRole::User, "What is Rust?"),
                 ])
                 .temperature(0.9)
                 .build()
@@ -123,12 +143,11 @@ mod tests {
             &Client::builder()
                 .timeout(Duration::from_secs(60))
                 .build()?,
+            OPENAI_API_BASE,
+            &EnvCredentialProvider.get_credentials().await?,
             &OpenAIChatRequestBody::builder()
                 .messages(vec![
-                    OpenAIChatMessage{
-                        role: OpenAIChatRole::User,
-                        content: "What is Rust?".to_string(),
-                    },
+                    OpenAIChatMessage::new(OpenAIChatRole::User, "What is Rust?"),
                 ])
                 .temperature(0.0)
                 .build()
@@ -144,17 +163,13 @@ mod tests {
     #[tokio::test]
     async fn test_stream_response() -> Result<()> {
 
-        use super::OPENAI_API_KEY;
         use super::OpenAIChatRequestBody;
         use crate::openai::chat::{OpenAIChatMessage, OpenAIChatRole};
         use futures::StreamExt;
 
         let request_body = OpenAIChatRequestBody::builder()
             .messages(vec![
-                OpenAIChatMessage{
-                    role: OpenAIChatRole::User,
-                    content: "What is Rust?".to_string(),
-                }
+                OpenAIChatMessage::new(OpenAIChatRole::User, "What is Rust?")
             ])
             .temperature(0.0)
             .build();
@@ -172,7 +187,7 @@ mod tests {
             .timeout(Duration::from_secs(60))
             .build()?
             .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", OPENAI_API_KEY.as_str()))
+            .bearer_auth(EnvCredentialProvider.get_credentials().await?.openai_api_key()?)
             .json(&request_body)
             .send()
             .await?;
@@ -1,13 +1,56 @@
 use serde::{Serialize, Deserialize};
+use serde_with::skip_serializing_none;
+use super::OpenAIToolCall;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAIChatMessage {
     
     pub role: OpenAIChatRole,
-    pub content: String,
+
+    /// The content, which is absent in assistant messages that only call tools.
+    pub content: Option<String>,
+
+    /// Tools called by the assistant.
+    pub tool_calls: Option<Vec<OpenAIToolCall>>,
+
+    /// ID of the tool call that a tool message responds to.
+    pub tool_call_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl OpenAIChatMessage {
+    /// Create a message with text content.
+    pub fn new<S: AsRef<str>>(role: OpenAIChatRole, content: S) -> Self {
+        Self {
+            role,
+            content: Some(content.as_ref().to_string()),
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    /// Create an assistant message that calls tools.
+    pub fn tool_calls(tool_calls: Vec<OpenAIToolCall>) -> Self {
+        Self {
+            role: OpenAIChatRole::Assistant,
+            content: None,
+            tool_calls: Some(tool_calls),
+            tool_call_id: None,
+        }
+    }
+
+    /// Create a tool message with the result of a tool call.
+    pub fn tool_result<S: AsRef<str>, T: AsRef<str>>(tool_call_id: S, content: T) -> Self {
+        Self {
+            role: OpenAIChatRole::Tool,
+            content: Some(content.as_ref().to_string()),
+            tool_calls: None,
+            tool_call_id: Some(tool_call_id.as_ref().to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OpenAIChatRole {
     #[serde(rename = "system")]
     System,
@@ -17,4 +60,7 @@ pub enum OpenAIChatRole {
 
     #[serde(rename = "assistant")]
     Assistant,
-}
\ No newline at end of file
+
+    #[serde(rename = "tool")]
+    Tool,
+}
@@ -1,21 +1,37 @@
 mod message;
 pub use message::{OpenAIChatMessage, OpenAIChatRole};
 
+mod tool;
+pub use tool::{
+    OpenAIChatTool,
+    OpenAIToolType,
+    OpenAIFunctionDefinition,
+    OpenAIToolChoice,
+    OpenAIToolCall,
+    OpenAIFunctionCall,
+    OpenAIToolCallDelta,
+    OpenAIFunctionCallDelta,
+};
+
 mod response;
 pub use response::{
     OpenAIChatCompletion, 
     OpenAIChatCompletionChunk, 
     OpenAIChatCompletionStream,
+    OpenAIChatTokenUsage,
 };
 
 mod model_names;
 pub use model_names::OpenAIChatModelName;
 
+mod response_format;
+pub use response_format::{OpenAIResponseFormat, OpenAIJsonSchema};
+
 mod request_body;
-pub use request_body::OpenAIChatRequestBody;
+pub use request_body::{OpenAIChatRequestBody, OpenAIStreamOptions};
 
 mod api_call;
 pub use api_call::{
     get_complete_chat_response,
-    // get_streamed_chat_response,
+    get_streamed_chat_response,
 };
@@ -1,17 +1,60 @@
+use std::collections::BTreeMap;
 use serde::Serialize;
 use serde_with::skip_serializing_none;
-use super::OpenAIChatMessage;
+use super::{
+    OpenAIChatMessage,
+    OpenAIChatTool,
+    OpenAIToolChoice,
+    OpenAIResponseFormat,
+};
 
 #[skip_serializing_none]
 #[derive(Debug, Serialize)]
 pub struct OpenAIChatRequestBody {
     pub model: String,
     pub messages: Vec<OpenAIChatMessage>,
-    pub temperature: f32,
-    pub top_p: f32,
-    pub presence_penalty: f32,
-    pub system: Option<String>,
-    pub user_id: Option<String>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub presence_penalty: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+
+    /// Maximum number of tokens to generate.
+    pub max_tokens: Option<u32>,
+
+    /// Sequences where the model stops generating, up to 4.
+    pub stop: Option<Vec<String>>,
+
+    /// Number of choices to generate.
+    pub n: Option<u32>,
+
+    /// Seed with which repeated requests return the same result on a best-effort basis.
+    pub seed: Option<i64>,
+
+    /// Bias from -100 to 100 added to the likelihood of tokens, keyed by token IDs.
+    pub logit_bias: Option<BTreeMap<u32, i32>>,
+
+    pub response_format: Option<OpenAIResponseFormat>,
+
+    /// Whether the log probabilities of the output tokens are returned.
+    pub logprobs: Option<bool>,
+
+    /// Number of most likely tokens, from 0 to 20, returned at each position with `logprobs`.
+    pub top_logprobs: Option<u32>,
+
+    pub stream_options: Option<OpenAIStreamOptions>,
+
+    /// A unique identifier of the end user, which helps OpenAI to detect abuse.
+    pub user: Option<String>,
+
+    pub tools: Option<Vec<OpenAIChatTool>>,
+    pub tool_choice: Option<OpenAIToolChoice>,
+}
+
+/// Options of a streamed response.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAIStreamOptions {
+    /// Whether a last chunk with the token usage of the whole request is streamed before `data: [DONE]`.
+    pub include_usage: bool,
 }
 
 impl OpenAIChatRequestBody {
@@ -19,11 +62,10 @@ impl OpenAIChatRequestBody {
     pub fn new(
         model: &str,
         messages: Vec<OpenAIChatMessage>,
-        temperature: f32,
-        top_p: f32,
-        presence_penalty: f32,
-        system: Option<String>,
-        user_id: Option<String>,
+        temperature: Option<f32>,
+        top_p: Option<f32>,
+        presence_penalty: Option<f32>,
+        user: Option<String>,
     ) -> Self {
         Self {
             model: model.to_string(),
@@ -31,8 +73,19 @@ impl OpenAIChatRequestBody {
             temperature,
             top_p,
             presence_penalty,
-            system,
-            user_id,
+            frequency_penalty: None,
+            max_tokens: None,
+            stop: None,
+            n: None,
+            seed: None,
+            logit_bias: None,
+            response_format: None,
+            logprobs: None,
+            top_logprobs: None,
+            stream_options: None,
+            user,
+            tools: None,
+            tool_choice: None,
         }
     }   
 
@@ -46,11 +99,22 @@ impl OpenAIChatRequestBody {
 pub struct OpenAIChatRequestBodyBuilder {
     model: String,
     messages: Vec<OpenAIChatMessage>,
-    temperature: f32,
-    top_p: f32,
-    presence_penalty: f32,
-    system: Option<String>,
-    user_id: Option<String>,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    presence_penalty: Option<f32>,
+    frequency_penalty: Option<f32>,
+    max_tokens: Option<u32>,
+    stop: Option<Vec<String>>,
+    n: Option<u32>,
+    seed: Option<i64>,
+    logit_bias: Option<BTreeMap<u32, i32>>,
+    response_format: Option<OpenAIResponseFormat>,
+    logprobs: Option<bool>,
+    top_logprobs: Option<u32>,
+    stream_options: Option<OpenAIStreamOptions>,
+    user: Option<String>,
+    tools: Option<Vec<OpenAIChatTool>>,
+    tool_choice: Option<OpenAIToolChoice>,
 }
 
 impl OpenAIChatRequestBodyBuilder {
@@ -58,11 +122,22 @@ impl OpenAIChatRequestBodyBuilder {
         Self {
             model: "gpt-3.5-turbo".to_string(),
             messages: vec![],
-            temperature: 0.0,
-            top_p: 0.0,
-            presence_penalty: 0.0,
-            system: None,
-            user_id: None,
+            temperature: None,
+            top_p: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            max_tokens: None,
+            stop: None,
+            n: None,
+            seed: None,
+            logit_bias: None,
+            response_format: None,
+            logprobs: None,
+            top_logprobs: None,
+            stream_options: None,
+            user: None,
+            tools: None,
+            tool_choice: None,
         }
     }
 
@@ -80,25 +155,97 @@ impl OpenAIChatRequestBodyBuilder {
 
     /// Set temperature, which controls the randomness of the output.
     pub fn temperature(mut self, temperature: f32) -> Self {
-        self.temperature = temperature;
+        self.temperature = Some(temperature);
         self
     }
 
     /// Set top_p, which controls the diversity of the output via nucleus sampling.
     pub fn top_p(mut self, top_p: f32) -> Self {
-        self.top_p = top_p;
+        self.top_p = Some(top_p);
         self
     }
 
     /// Set presence_penalty, which penalizes new tokens based on whether they appear in the text so far.
     pub fn presence_penalty(mut self, presence_penalty: f32) -> Self {
-        self.presence_penalty = presence_penalty;
+        self.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Set frequency_penalty, which penalizes new tokens based on how often they appear in the text so far.
+    pub fn frequency_penalty(mut self, frequency_penalty: f32) -> Self {
+        self.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    /// Set the maximum number of tokens to generate.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
         self
     }
 
-    /// Set user ID.
-    pub fn user_id(mut self, user_id: &str) -> Self {
-        self.user_id = Some(user_id.to_string());
+    /// Set sequences where the model stops generating.
+    pub fn stop(mut self, stop: Vec<String>) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    /// Set the number of choices to generate.
+    pub fn n(mut self, n: u32) -> Self {
+        self.n = Some(n);
+        self
+    }
+
+    /// Set seed, which makes the output deterministic on a best-effort basis.
+    pub fn seed(mut self, seed: i64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the bias added to the likelihood of tokens, keyed by token IDs.
+    pub fn logit_bias(mut self, logit_bias: BTreeMap<u32, i32>) -> Self {
+        self.logit_bias = Some(logit_bias);
+        self
+    }
+
+    /// Set the format that the output must follow.
+    pub fn response_format(mut self, response_format: OpenAIResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    /// Set whether the log probabilities of the output tokens are returned.
+    pub fn logprobs(mut self, logprobs: bool) -> Self {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
+    /// Set the number of most likely tokens returned at each position.
+    pub fn top_logprobs(mut self, top_logprobs: u32) -> Self {
+        self.top_logprobs = Some(top_logprobs);
+        self
+    }
+
+    /// Set options of a streamed response.
+    pub fn stream_options(mut self, stream_options: OpenAIStreamOptions) -> Self {
+        self.stream_options = Some(stream_options);
+        self
+    }
+
+    /// Set the unique identifier of the end user.
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = Some(user.to_string());
+        self
+    }
+
+    /// Set tools that the model may call.
+    pub fn tools(mut self, tools: Vec<OpenAIChatTool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Set which tool, if any, is called by the model.
+    pub fn tool_choice(mut self, tool_choice: OpenAIToolChoice) -> Self {
+        self.tool_choice = Some(tool_choice);
         self
     }
 
@@ -109,8 +256,139 @@ impl OpenAIChatRequestBodyBuilder {
             temperature: self.temperature,
             top_p: self.top_p,
             presence_penalty: self.presence_penalty,
-            system: self.system,
-            user_id: self.user_id,
+            frequency_penalty: self.frequency_penalty,
+            max_tokens: self.max_tokens,
+            stop: self.stop,
+            n: self.n,
+            seed: self.seed,
+            logit_bias: self.logit_bias,
+            response_format: self.response_format,
+            logprobs: self.logprobs,
+            top_logprobs: self.top_logprobs,
+            stream_options: self.stream_options,
+            user: self.user,
+            tools: self.tools,
+            tool_choice: self.tool_choice,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde_json::json;
+    use crate::openai::chat::{
+        OpenAIChatMessage,
+        OpenAIChatRole,
+        OpenAIJsonSchema,
+        OpenAIResponseFormat,
+    };
+    use super::{
+        OpenAIChatRequestBody,
+        OpenAIStreamOptions,
+    };
+
+    #[test]
+    fn test_serialize_default_request_body() {
+        let request_body = OpenAIChatRequestBody::builder()
+            .messages(vec![
+                OpenAIChatMessage::new(OpenAIChatRole::User, "What is Rust?"),
+            ])
+            .build();
+
+        // Unset parameters are left to the defaults of OpenAI
+        assert_eq!(
+            serde_json::to_value(request_body).unwrap(),
+            json!({
+                "model": "gpt-3.5-turbo",
+                "messages": [
+                    {"role": "user", "content": "What is Rust?"},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialize_all_parameters() {
+        let request_body = OpenAIChatRequestBody::builder()
+            .model("gpt-4")
+            .messages(vec![
+                OpenAIChatMessage::new(OpenAIChatRole::User, "What is Rust?"),
+            ])
+            .temperature(0.5)
+            .top_p(0.25)
+            .presence_penalty(0.5)
+            .frequency_penalty(-0.5)
+            .max_tokens(256)
+            .stop(vec!["\n\n".to_string(), "END".to_string()])
+            .n(2)
+            .seed(42)
+            .logit_bias(BTreeMap::from([(50256, -100), (1234, 5)]))
+            .response_format(OpenAIResponseFormat::JsonObject)
+            .logprobs(true)
+            .top_logprobs(3)
+            .stream_options(OpenAIStreamOptions {
+                include_usage: true,
+            })
+            .user("user-1234")
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(request_body).unwrap(),
+            json!({
+                "model": "gpt-4",
+                "messages": [
+                    {"role": "user", "content": "What is Rust?"},
+                ],
+                "temperature": 0.5,
+                "top_p": 0.25,
+                "presence_penalty": 0.5,
+                "frequency_penalty": -0.5,
+                "max_tokens": 256,
+                "stop": ["\n\n", "END"],
+                "n": 2,
+                "seed": 42,
+                "logit_bias": {"1234": 5, "50256": -100},
+                "response_format": {"type": "json_object"},
+                "logprobs": true,
+                "top_logprobs": 3,
+                "stream_options": {"include_usage": true},
+                "user": "user-1234",
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialize_response_formats() {
+        assert_eq!(
+            serde_json::to_value(OpenAIResponseFormat::Text).unwrap(),
+            json!({"type": "text"})
+        );
+        assert_eq!(
+            serde_json::to_value(OpenAIResponseFormat::JsonSchema {
+                json_schema: OpenAIJsonSchema {
+                    strict: Some(true),
+                    ..OpenAIJsonSchema::new("weather", json!({
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string"},
+                        },
+                    }))
+                },
+            }).unwrap(),
+            json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "weather",
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "city": {"type": "string"},
+                        },
+                    },
+                    "strict": true,
+                },
+            })
+        );
+    }
+}
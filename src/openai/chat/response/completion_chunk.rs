@@ -1,5 +1,11 @@
 use serde::Deserialize;
-use super::super::OpenAIChatRole;
+use super::{
+    super::{
+        OpenAIChatRole,
+        OpenAIToolCallDelta,
+    },
+    OpenAIChatTokenUsage,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct OpenAIChatCompletionChunk {
@@ -8,6 +14,10 @@ pub struct OpenAIChatCompletionChunk {
     pub created: i64,
     pub model: String,
     pub object: String,
+
+    /// Token usage of the whole request, which is only sent in the last chunk
+    /// with no choices if `stream_options.include_usage` is set.
+    pub usage: Option<OpenAIChatTokenUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,6 +31,7 @@ pub struct OpenAIChatCompletionChunkChoice {
 pub struct OpenAIChatCompletionChunkChoiceDelta {
     pub content: Option<String>,
     pub role: Option<OpenAIChatRole>,
+    pub tool_calls: Option<Vec<OpenAIToolCallDelta>>,
 }
 
 
@@ -1,75 +1,28 @@
-use anyhow::Result;
-use tracing::error;
 use futures::{Stream, StreamExt};
 use std::{
     pin::Pin, 
     task::{Context, Poll}
 };
 use bytes::Bytes;
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::{
+    SseStream,
+    UnilangError,
+    Result,
+    openai::error::parse_stream_error,
+};
 use super::OpenAIChatCompletionChunk;
 
-lazy_static! {
-    static ref STREAM_RESPONSE_CHUNK_RE: Regex = Regex::new(r"^data: \{.*\}\n\n").unwrap();
-    static ref STREAM_RESPONSE_TERMINATION_CHUNK_RE: Regex = Regex::new(r"^data: \[DONE\]\n\n").unwrap();
-}
-
-fn extract_first_chunk(content: &str) -> Result<ExtractedChunkWithRemainingContent> {
-    if let Some(mat) = STREAM_RESPONSE_CHUNK_RE.find(content) {
-        // Matched string
-        let matched_str = mat.as_str();
-
-        // Remaining content
-        let remaining_content = &content[mat.end()..];
-
-        // Extract the JSON content
-        let json_content = matched_str.strip_prefix("data: ").unwrap();
-
-        // Parse the JSON content to OpenAIChatCompletionChunk
-        let chunk = serde_json::from_str(json_content)?;
-
-        // Return the extracted response and remaining content
-        Ok(
-            ExtractedChunkWithRemainingContent {
-                chunk: Some(chunk),
-
-                // If there is no remaining content, return None
-                remaining_content: match remaining_content.len() {
-                    0 => None,
-                    _ => Some(remaining_content.to_string()),
-                },
-            }
-        )
-    } else if let Some(_) = STREAM_RESPONSE_TERMINATION_CHUNK_RE.find(content) {
-        // The current chunk is the termination chunk, "data: [DONE]\n\n", from OpenAI
-        Ok(
-            ExtractedChunkWithRemainingContent { chunk: None, remaining_content: None }
-        )
-    } else {
-        // There is no response in the json_content,
-        // so return None for the response and the json_content as the remaining content
-        Ok(
-            ExtractedChunkWithRemainingContent {
-                chunk: None,
-                remaining_content: match content.len() {
-                    0 => None,
-                    _ => Some(content.to_string()),
-                },
-            }
-        )
-    }
-}
-
-#[derive(Debug)]
-pub struct ExtractedChunkWithRemainingContent {
-    pub chunk: Option<OpenAIChatCompletionChunk>,
-    pub remaining_content: Option<String>,
-}
+/// The data of the event with which OpenAI ends the stream.
+const TERMINATION_DATA: &str = "[DONE]";
 
+/// A stream of chunks of a chat completion.
+/// 
+/// Transport errors, errors sent by OpenAI in the stream
+/// and the end of the stream before the termination event are yielded as errors,
+/// after which the stream ends.
 pub struct OpenAIChatCompletionStream<S> {
-    response_bytes_stream: S,
-    remaining_content: Option<String>,
+    event_stream: SseStream<S>,
+    is_terminated: bool,
 }
 
 impl<S> OpenAIChatCompletionStream<S>
@@ -78,8 +31,8 @@ where
 {
     pub fn new(response_bytes_stream: S) -> Self {
         Self { 
-            response_bytes_stream, 
-            remaining_content: None,
+            event_stream: SseStream::new(response_bytes_stream),
+            is_terminated: false,
         }
     }
 }
@@ -88,95 +41,118 @@ impl<S> Stream for OpenAIChatCompletionStream<S>
 where
     S: Stream<Item = Result<Bytes, reqwest::Error>> + Unpin
 {
-    type Item = OpenAIChatCompletionChunk;
+    type Item = Result<OpenAIChatCompletionChunk>;
 
     fn poll_next(
         mut self: Pin<&mut Self>, 
         cx: &mut Context<'_>
     ) -> Poll<Option<Self::Item>> {
-        loop {
-            match self.response_bytes_stream.poll_next_unpin(cx) {
-                Poll::Ready(Some(Ok(bytes))) => {
-                    // Convert bytes to string
-                    let mut content = std::str::from_utf8(&bytes).unwrap().to_string();
-
-                    // Concatenate the remaining content if there is any
-                    if let Some(remaining_content) = &self.remaining_content {
-                        // Put the remaining content in front of the currently received content
-                        content.insert_str(0, remaining_content);
-                    }
-
-                    // Extract the first response and remaining content
-                    let response_with_remaining_content = extract_first_chunk(&content).unwrap();
-                
-                    // Get the response and remaining content
-                    let chunk = response_with_remaining_content.chunk;
-                    let remaining_content = response_with_remaining_content.remaining_content;
-                    
-                    // Collect the remaining content if there is any
-                    if let Some(remaining_content) = &remaining_content {
-                        self.remaining_content = Some(remaining_content.to_owned());
-                    } else {
-                        self.remaining_content = None;
-                    }
-
-                    // Return Ready if there is one response
-                    if let Some(chunk) = chunk {
-                        return Poll::Ready(Some(chunk));
-                    } else if remaining_content.is_none() {
-                        // If both the chunk and the remaining content are None,
-                        // then it means that the current chunk
-                        // is the termination chunk, "data: [DONE]\n\n", from OpenAI
-                        return Poll::Ready(None);
-                    } else {
-                        continue;
-                        // return Poll::Pending;
-                    }
-                },
-                Poll::Ready(Some(Err(e))) => {
-                    error!("The response is incomplete - {}", e);
-                    return Poll::Ready(None);
-                },
-                Poll::Ready(None) => {
-                    // All the chunks from OpenAI have been received
-                    // But chances are that there are still some remaining content
-                    // that has not been parsed yet
-                    // So we handle the remaining content here
-                    if let Some(remaining_content) = &self.remaining_content {
-                        // Extract the first response and remaining content
-                        let response_with_remaining_content = extract_first_chunk(&remaining_content).unwrap();
-                    
-                        // Get the response and remaining content
-                        let chunk = response_with_remaining_content.chunk;
-                        let remaining_content = response_with_remaining_content.remaining_content;
-                        
-                        // Collect the remaining content if there is any
-                        if let Some(remaining_content) = &remaining_content {
-                            self.remaining_content = Some(remaining_content.to_owned());
-                        } else {
-                            self.remaining_content = None;
-                        }
-
-                        // Return Ready if there is one response
-                        if let Some(chunk) = chunk {
-                            return Poll::Ready(Some(chunk));
-                        } else if remaining_content.is_none() {
-                            // If both the chunk and the remaining content are None,
-                            // then it means that the current chunk
-                            // is the termination chunk, "data: [DONE]\n\n", from OpenAI
-                            return Poll::Ready(None);
-                        } else {
-                            continue;
-                        }
-                    }
-
-                    // If there is no remaining content, then return None
-                    return Poll::Ready(None);
-                },
-                Poll::Pending => {
-                    return Poll::Pending;
+        if self.is_terminated {
+            return Poll::Ready(None);
+        }
+
+        let item = match futures::ready!(self.event_stream.poll_next_unpin(cx)) {
+            Some(Ok(event)) if event.data == TERMINATION_DATA => None,
+            Some(Ok(event)) => match serde_json::from_str::<OpenAIChatCompletionChunk>(&event.data) {
+                Ok(chunk) => Some(Ok(chunk)),
+                Err(error) => match parse_stream_error(&event.data) {
+                    Some(openai_error) => Some(Err(openai_error.into())),
+                    None => Some(Err(error.into())),
                 },
-            }
+            },
+            Some(Err(error)) => Some(Err(error.into())),
+            None => Some(Err(UnilangError::IncompleteStream(
+                "OpenAI closed the stream without the termination event".to_string()
+            ))),
+        };
+
+        // Nothing is returned after the termination event or an error
+        self.is_terminated = !matches!(item, Some(Ok(_)));
+
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::Bytes;
+    use futures::{stream, Stream, StreamExt};
+    use crate::UnilangError;
+    use super::{
+        OpenAIChatCompletionChunk,
+        OpenAIChatCompletionStream,
+    };
+
+    const CONTENT: &str = concat!(
+        ": OPENROUTER PROCESSING\r\n\r\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-3.5-turbo\",",
+        "\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"你好\"},\"finish_reason\":null}]}\r\n\r\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-3.5-turbo\",",
+        "\"choices\":[{\"index\":0,\"delta\":{},\"finish_reason\":\"stop\"}]}\r\n\r\n",
+        "data: [DONE]\r\n\r\n",
+    );
+
+    fn create_stream(content: &[u8], chunk_size: usize) -> impl Stream<Item = crate::Result<OpenAIChatCompletionChunk>> {
+        OpenAIChatCompletionStream::new(stream::iter(
+            content
+                .chunks(chunk_size)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>()
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_split_chunks() -> Result<()> {
+        // Split within the Chinese characters and the line endings
+        for chunk_size in 1..=8 {
+            let chunks = create_stream(CONTENT.as_bytes(), chunk_size)
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            assert_eq!(chunks.len(), 2);
+            assert_eq!(chunks[0].choices[0].delta.content.as_deref(), Some("你好"));
+            assert_eq!(chunks[1].choices[0].finish_reason.as_deref(), Some("stop"));
         }
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_truncated_stream() {
+        let content = CONTENT.strip_suffix("data: [DONE]\r\n\r\n").unwrap();
+
+        let items = create_stream(content.as_bytes(), 16)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items.len(), 3);
+        assert!(items[1].is_ok());
+        assert!(matches!(items[2], Err(UnilangError::IncompleteStream(_))));
+    }
+
+    #[tokio::test]
+    async fn test_stream_error() {
+        let content = concat!(
+            "data: {\"error\":{\"message\":\"The server had an error\",\"type\":\"server_error\",\"param\":null,\"code\":null}}\n\n",
+            "data: [DONE]\n\n",
+        );
+
+        let items = create_stream(content.as_bytes(), 16)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items.len(), 1);
+        assert!(matches!(&items[0], Err(UnilangError::OpenAI(error)) if error.message == "The server had an error"));
+
+        // Neither a chunk nor an error
+        let items = create_stream(b"data: {\"id\":1}\n\n", 16)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items.len(), 1);
+        assert!(matches!(items[0], Err(UnilangError::Parse(_))));
+    }
+}
@@ -0,0 +1,45 @@
+use serde::Serialize;
+use serde_json::Value;
+use serde_with::skip_serializing_none;
+
+/// The format that the output of the model must follow.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpenAIResponseFormat {
+    /// Plain text, which is the default.
+    Text,
+
+    /// Any valid JSON object.
+    /// The messages must also instruct the model to produce JSON.
+    JsonObject,
+
+    /// JSON that matches the given schema.
+    JsonSchema {
+        json_schema: OpenAIJsonSchema,
+    },
+}
+
+/// A JSON Schema that the output of the model must match.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAIJsonSchema {
+    pub name: String,
+    pub description: Option<String>,
+
+    /// The schema described as a JSON Schema object.
+    pub schema: Value,
+
+    /// Whether the output must match the schema exactly.
+    pub strict: Option<bool>,
+}
+
+impl OpenAIJsonSchema {
+    pub fn new<S: AsRef<str>>(name: S, schema: Value) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            description: None,
+            schema,
+            strict: None,
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Serialize, Serializer, Deserialize};
+use serde_json::{json, Value};
+use serde_with::skip_serializing_none;
+
+/// A tool that the model may call.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAIChatTool {
+    #[serde(rename = "type")]
+    pub tool_type: OpenAIToolType,
+
+    pub function: OpenAIFunctionDefinition,
+}
+
+impl OpenAIChatTool {
+    /// Create a function tool.
+    pub fn function(function: OpenAIFunctionDefinition) -> Self {
+        Self {
+            tool_type: OpenAIToolType::Function,
+            function,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OpenAIToolType {
+    #[serde(rename = "function")]
+    Function,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenAIFunctionDefinition {
+    pub name: String,
+    pub description: Option<String>,
+
+    /// Parameters of the function described as a JSON Schema object.
+    pub parameters: Value,
+}
+
+/// Controls which tool, if any, is called by the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenAIToolChoice {
+    /// The model does not call any tool.
+    None,
+
+    /// The model decides whether to call tools.
+    Auto,
+
+    /// The model must call the function with the given name.
+    Function(String),
+}
+
+impl Serialize for OpenAIToolChoice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OpenAIToolChoice::None => serializer.serialize_str("none"),
+            OpenAIToolChoice::Auto => serializer.serialize_str("auto"),
+            OpenAIToolChoice::Function(name) => json!({
+                "type": "function",
+                "function": { "name": name },
+            })
+            .serialize(serializer),
+        }
+    }
+}
+
+/// A tool call requested by the model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIToolCall {
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub tool_type: OpenAIToolType,
+
+    pub function: OpenAIFunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIFunctionCall {
+    pub name: String,
+
+    /// Arguments generated by the model in JSON, which may not be valid.
+    pub arguments: String,
+}
+
+/// A piece of a tool call in a streamed response.
+/// 
+/// Pieces with the same index belong to the same tool call.
+/// The ID, type and name come with the first piece,
+/// while the arguments are split across the pieces.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenAIToolCallDelta {
+    pub index: u32,
+    pub id: Option<String>,
+
+    #[serde(rename = "type")]
+    pub tool_type: Option<OpenAIToolType>,
+
+    pub function: Option<OpenAIFunctionCallDelta>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenAIFunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+    use crate::openai::chat::{
+        OpenAIChatCompletion,
+        OpenAIChatCompletionChunk,
+        OpenAIChatMessage,
+        OpenAIChatRequestBody,
+        OpenAIChatRole,
+    };
+    use super::{
+        OpenAIChatTool,
+        OpenAIFunctionDefinition,
+        OpenAIToolChoice,
+    };
+
+    #[test]
+    fn test_serialize_tools() -> Result<()> {
+        let request_body = OpenAIChatRequestBody::builder()
+            .messages(vec![
+                OpenAIChatMessage::new(OpenAIChatRole::User, "What's the weather like in Paris?"),
+            ])
+            .tools(vec![
+                OpenAIChatTool::function(OpenAIFunctionDefinition {
+                    name: "get_weather".to_string(),
+                    description: Some("Get the current weather of a city".to_string()),
+                    parameters: json!({
+                        "type": "object",
+                        "properties": {
+                            "city": { "type": "string" },
+                        },
+                        "required": ["city"],
+                    }),
+                }),
+            ])
+            .tool_choice(OpenAIToolChoice::Function("get_weather".to_string()))
+            .build();
+
+        let value = serde_json::to_value(request_body)?;
+
+        assert_eq!(
+            value["tools"],
+            json!([{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Get the current weather of a city",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "city": { "type": "string" },
+                        },
+                        "required": ["city"],
+                    },
+                },
+            }])
+        );
+        assert_eq!(
+            value["tool_choice"],
+            json!({ "type": "function", "function": { "name": "get_weather" } })
+        );
+        assert_eq!(serde_json::to_value(OpenAIToolChoice::Auto)?, json!("auto"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_tool_messages() -> Result<()> {
+        let completion: OpenAIChatCompletion = serde_json::from_str(
+            r#"{"id":"chatcmpl-1","object":"chat.completion","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"message":{"role":"assistant","content":null,"tool_calls":[{"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"city\":\"Paris\"}"}}]},"finish_reason":"tool_calls"}],"usage":{"prompt_tokens":80,"completion_tokens":16,"total_tokens":96}}"#
+        )?;
+
+        // Send the tool calls back with the result
+        let message = completion.choices[0].message.clone();
+        let tool_call_id = message.tool_calls.as_ref().unwrap()[0].id.to_owned();
+        let messages = vec![
+            message,
+            OpenAIChatMessage::tool_result(tool_call_id, "Sunny, 25°C"),
+        ];
+
+        assert_eq!(
+            serde_json::to_value(messages)?,
+            json!([
+                {
+                    "role": "assistant",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" },
+                    }],
+                },
+                {
+                    "role": "tool",
+                    "content": "Sunny, 25°C",
+                    "tool_call_id": "call_1",
+                },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_tool_call_deltas() -> Result<()> {
+        let chunks = [
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"role":"assistant","content":null,"tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":""}}]},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"{\"city\":"}}]},"finish_reason":null}]}"#,
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"tool_calls":[{"index":0,"function":{"arguments":"\"Paris\"}"}}]},"finish_reason":null}]}"#,
+        ];
+
+        let arguments = chunks
+            .iter()
+            .map(|chunk| serde_json::from_str::<OpenAIChatCompletionChunk>(chunk))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flat_map(|chunk| chunk.choices)
+            .flat_map(|choice| choice.delta.tool_calls.unwrap_or_default())
+            .filter_map(|tool_call| tool_call.function.and_then(|function| function.arguments))
+            .collect::<String>();
+
+        assert_eq!(arguments, r#"{"city":"Paris"}"#);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use reqwest::Client;
+use crate::{Credentials, Result};
+use super::{
+    super::error::parse_error_response,
+    OpenAIEmbeddingRequestBody,
+    OpenAIEmbeddingResponse,
+};
+
+/// Call OpenAI embedding API and return the embeddings of the input.
+/// 
+/// The API is called at `api_base`, which is `OPENAI_API_BASE` unless a proxy is used.
+pub async fn get_embeddings(
+    client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
+    request_body: &OpenAIEmbeddingRequestBody,
+) -> Result<OpenAIEmbeddingResponse> {
+    // Call API to get embeddings
+    let response = client
+        .post(format!("{}/embeddings", api_base))
+        .bearer_auth(credentials.openai_api_key()?)
+        .json(request_body)
+        .send()
+        .await?;
+
+    // Get the response status, headers and content
+    let status = response.status();
+    let headers = response.headers().clone();
+    let response_content = response.text().await?;
+
+    // Parse the response content as OpenAIEmbeddingResponse or OpenAIError
+    if status.is_success() {
+        Ok(serde_json::from_str::<OpenAIEmbeddingResponse>(&response_content)?)
+    } else {
+        Err(parse_error_response(status.as_u16(), &headers, response_content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use reqwest::Client;
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::{header, method, path},
+    };
+    use crate::{
+        Credentials,
+        UnilangError,
+        openai::OpenAIErrorKind,
+    };
+    use super::{
+        get_embeddings,
+        OpenAIEmbeddingRequestBody,
+    };
+
+    #[tokio::test]
+    async fn test_get_embeddings_with_mock_server() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    {"object": "embedding", "index": 0, "embedding": [0.5, -0.25]},
+                    {"object": "embedding", "index": 1, "embedding": [0.25, 1.0]},
+                ],
+                "model": "text-embedding-3-small",
+                "usage": {"prompt_tokens": 8, "total_tokens": 8},
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": {
+                    "message": "Incorrect API key provided",
+                    "type": "invalid_request_error",
+                    "param": null,
+                    "code": "invalid_api_key",
+                },
+            })))
+            .mount(&server)
+            .await;
+        let credentials = Credentials::builder()
+            .openai_api_key("sk-test")
+            .build();
+        let request_body = OpenAIEmbeddingRequestBody::builder()
+            .model("text-embedding-3-small")
+            .inputs(vec!["What is Rust?".to_string(), "What is Go?".to_string()])
+            .build();
+
+        let response = get_embeddings(&Client::new(), &server.uri(), &credentials, &request_body).await?;
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[1].embedding, vec![0.25, 1.0]);
+        assert_eq!(response.usage.prompt_tokens, 8);
+
+        match get_embeddings(&Client::new(), &server.uri(), &credentials, &request_body).await {
+            Err(UnilangError::OpenAI(error)) => assert_eq!(error.kind(), OpenAIErrorKind::InvalidApiKey),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(
+            requests[0].body_json::<serde_json::Value>()?,
+            json!({"model": "text-embedding-3-small", "input": ["What is Rust?", "What is Go?"]})
+        );
+
+        Ok(())
+    }
+}
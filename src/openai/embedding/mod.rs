@@ -0,0 +1,16 @@
+mod request_body;
+pub use request_body::{
+    OpenAIEmbeddingRequestBody,
+    OpenAIEmbeddingInput,
+    OpenAIEmbeddingEncodingFormat,
+};
+
+mod response;
+pub use response::{
+    OpenAIEmbeddingResponse,
+    OpenAIEmbedding,
+    OpenAIEmbeddingTokenUsage,
+};
+
+mod api_call;
+pub use api_call::get_embeddings;
@@ -0,0 +1,153 @@
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct OpenAIEmbeddingRequestBody {
+    pub model: String,
+    pub input: OpenAIEmbeddingInput,
+    pub encoding_format: Option<OpenAIEmbeddingEncodingFormat>,
+
+    /// Number of dimensions of the output embeddings,
+    /// which is only supported by text-embedding-3 and later models.
+    pub dimensions: Option<u32>,
+
+    pub user: Option<String>,
+}
+
+impl OpenAIEmbeddingRequestBody {
+    pub fn builder() -> OpenAIEmbeddingRequestBodyBuilder {
+        OpenAIEmbeddingRequestBodyBuilder::new()
+    }
+}
+
+/// Text to embed, either a single string or a batch of strings.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum OpenAIEmbeddingInput {
+    Single(String),
+    Batch(Vec<String>),
+}
+
+/// Format in which the embeddings are returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OpenAIEmbeddingEncodingFormat {
+    #[serde(rename = "float")]
+    Float,
+
+    /// Little-endian 32-bit floats encoded in base64, which is much smaller than JSON numbers.
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+pub struct OpenAIEmbeddingRequestBodyBuilder {
+    model: String,
+    input: OpenAIEmbeddingInput,
+    encoding_format: Option<OpenAIEmbeddingEncodingFormat>,
+    dimensions: Option<u32>,
+    user: Option<String>,
+}
+
+impl Default for OpenAIEmbeddingRequestBodyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpenAIEmbeddingRequestBodyBuilder {
+    pub fn new() -> Self {
+        Self {
+            model: "text-embedding-ada-002".to_string(),
+            input: OpenAIEmbeddingInput::Batch(vec![]),
+            encoding_format: None,
+            dimensions: None,
+            user: None,
+        }
+    }
+
+    /// Set model name.
+    pub fn model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    /// Set a single text to embed.
+    pub fn input<S: AsRef<str>>(mut self, input: S) -> Self {
+        self.input = OpenAIEmbeddingInput::Single(input.as_ref().to_string());
+        self
+    }
+
+    /// Set a batch of texts to embed.
+    pub fn inputs(mut self, inputs: Vec<String>) -> Self {
+        self.input = OpenAIEmbeddingInput::Batch(inputs);
+        self
+    }
+
+    /// Set the format in which the embeddings are returned.
+    pub fn encoding_format(mut self, encoding_format: OpenAIEmbeddingEncodingFormat) -> Self {
+        self.encoding_format = Some(encoding_format);
+        self
+    }
+
+    /// Set the number of dimensions of the output embeddings.
+    pub fn dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Set the ID of the end user.
+    pub fn user<S: AsRef<str>>(mut self, user: S) -> Self {
+        self.user = Some(user.as_ref().to_string());
+        self
+    }
+
+    pub fn build(self) -> OpenAIEmbeddingRequestBody {
+        OpenAIEmbeddingRequestBody {
+            model: self.model,
+            input: self.input,
+            encoding_format: self.encoding_format,
+            dimensions: self.dimensions,
+            user: self.user,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+    use super::{
+        OpenAIEmbeddingRequestBody,
+        OpenAIEmbeddingEncodingFormat,
+    };
+
+    #[test]
+    fn test_builder() -> Result<()> {
+        let request_body = OpenAIEmbeddingRequestBody::builder()
+            .model("text-embedding-3-small")
+            .input("What is Rust?")
+            .build();
+        assert_eq!(
+            serde_json::to_value(request_body)?,
+            json!({"model": "text-embedding-3-small", "input": "What is Rust?"})
+        );
+
+        let request_body = OpenAIEmbeddingRequestBody::builder()
+            .model("text-embedding-3-large")
+            .inputs(vec!["What is Rust?".to_string(), "What is Go?".to_string()])
+            .encoding_format(OpenAIEmbeddingEncodingFormat::Base64)
+            .dimensions(256)
+            .build();
+        assert_eq!(
+            serde_json::to_value(request_body)?,
+            json!({
+                "model": "text-embedding-3-large",
+                "input": ["What is Rust?", "What is Go?"],
+                "encoding_format": "base64",
+                "dimensions": 256,
+            })
+        );
+
+        Ok(())
+    }
+}
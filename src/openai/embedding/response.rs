@@ -0,0 +1,94 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Deserializer, de::Error};
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAIEmbeddingResponse {
+    pub object: String,
+    pub data: Vec<OpenAIEmbedding>,
+    pub model: String,
+    pub usage: OpenAIEmbeddingTokenUsage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAIEmbedding {
+    pub object: String,
+    pub index: usize,
+
+    /// The embedding vector, decoded from base64 if it is requested in that format.
+    #[serde(deserialize_with = "deserialize_embedding")]
+    pub embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAIEmbeddingTokenUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// Deserialize an embedding encoded either as an array of floats or as base64.
+fn deserialize_embedding<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum EncodedEmbedding {
+        Float(Vec<f32>),
+        Base64(String),
+    }
+
+    match EncodedEmbedding::deserialize(deserializer)? {
+        EncodedEmbedding::Float(embedding) => Ok(embedding),
+        EncodedEmbedding::Base64(encoded) => {
+            let bytes = STANDARD.decode(encoded).map_err(D::Error::custom)?;
+            if bytes.len() % 4 != 0 {
+                return Err(D::Error::custom("base64 embedding is not a sequence of 32-bit floats"));
+            }
+
+            Ok(
+                bytes
+                    .chunks_exact(4)
+                    .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use super::OpenAIEmbeddingResponse;
+
+    #[test]
+    fn test_deserialize_float_embeddings() -> Result<()> {
+        let response: OpenAIEmbeddingResponse = serde_json::from_str(
+            r#"{"object":"list","data":[{"object":"embedding","index":0,"embedding":[0.5,-0.25]}],"model":"text-embedding-3-small","usage":{"prompt_tokens":4,"total_tokens":4}}"#
+        )?;
+
+        assert_eq!(response.data[0].embedding, vec![0.5, -0.25]);
+        assert_eq!(response.usage.total_tokens, 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_base64_embeddings() -> Result<()> {
+        let encoded = STANDARD.encode(
+            [0.5f32, -0.25, 1.0]
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect::<Vec<u8>>()
+        );
+        let response: OpenAIEmbeddingResponse = serde_json::from_str(
+            &format!(r#"{{"object":"list","data":[{{"object":"embedding","index":0,"embedding":"{}"}}],"model":"text-embedding-3-small","usage":{{"prompt_tokens":4,"total_tokens":4}}}}"#, encoded)
+        )?;
+
+        assert_eq!(response.data[0].embedding, vec![0.5, -0.25, 1.0]);
+
+        // A truncated vector is rejected
+        assert!(serde_json::from_str::<OpenAIEmbeddingResponse>(
+            r#"{"object":"list","data":[{"object":"embedding","index":0,"embedding":"AAAA"}],"model":"text-embedding-3-small","usage":{"prompt_tokens":4,"total_tokens":4}}"#
+        ).is_err());
+
+        Ok(())
+    }
+}
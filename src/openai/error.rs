@@ -0,0 +1,152 @@
+use std::time::Duration;
+use reqwest::header::HeaderMap;
+use thiserror::Error;
+use serde::Deserialize;
+use crate::{
+    UnilangError,
+    retry::parse_retry_after,
+};
+
+#[derive(Debug, Error, Deserialize)]
+#[error("OpenAIError: {message}")]
+pub struct OpenAIError {
+    pub message: String,
+
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+
+    pub param: Option<String>,
+    pub code: Option<String>,
+
+    /// HTTP status of the response that carries the error.
+    #[serde(skip)]
+    pub status: Option<u16>,
+
+    /// Delay that OpenAI asks for before the request is sent again.
+    #[serde(skip)]
+    pub retry_after: Option<Duration>,
+}
+
+impl OpenAIError {
+    /// Get the kind of the error from its code and type.
+    pub fn kind(&self) -> OpenAIErrorKind {
+        match self.code.as_deref().or(self.error_type.as_deref()) {
+            Some("invalid_api_key") => OpenAIErrorKind::InvalidApiKey,
+            Some("insufficient_quota") => OpenAIErrorKind::InsufficientQuota,
+            Some("rate_limit_exceeded") => OpenAIErrorKind::RateLimitExceeded,
+            Some("context_length_exceeded") => OpenAIErrorKind::ContextLengthExceeded,
+            Some("model_not_found") => OpenAIErrorKind::ModelNotFound,
+            Some("server_error") => OpenAIErrorKind::ServerError,
+            _ => OpenAIErrorKind::Other,
+        }
+    }
+
+    /// Check whether the error is temporary.
+    /// An exhausted quota is reported with 429 as well, but it is not temporary.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind() {
+            OpenAIErrorKind::RateLimitExceeded | OpenAIErrorKind::ServerError => true,
+            OpenAIErrorKind::InsufficientQuota => false,
+            _ => self.status.is_some_and(|status| status == 429 || status >= 500),
+        }
+    }
+}
+
+/// Kinds of errors that OpenAI reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenAIErrorKind {
+    InvalidApiKey,
+    InsufficientQuota,
+    RateLimitExceeded,
+    ContextLengthExceeded,
+    ModelNotFound,
+    ServerError,
+    Other,
+}
+
+/// The body of an unsuccessful response from OpenAI,
+/// which wraps the error in the key "error".
+#[derive(Debug, Deserialize)]
+struct OpenAIErrorResponse {
+    error: OpenAIError,
+}
+
+/// Convert the content of an unsuccessful response to an error.
+/// 
+/// If the content is not an OpenAI error,
+/// then the HTTP status and the raw content are returned instead.
+pub(crate) fn parse_error_response(status: u16, headers: &HeaderMap, response_content: String) -> UnilangError {
+    let retry_after = parse_retry_after(headers);
+
+    match serde_json::from_str::<OpenAIErrorResponse>(&response_content) {
+        Ok(OpenAIErrorResponse { mut error }) => {
+            error.status = Some(status);
+            error.retry_after = retry_after;
+            error.into()
+        },
+        Err(_) => UnilangError::HttpStatus {
+            status,
+            body: response_content,
+            retry_after,
+        },
+    }
+}
+
+/// Parse an error that OpenAI sends in the event stream instead of a chunk.
+pub(crate) fn parse_stream_error(data: &str) -> Option<OpenAIError> {
+    serde_json::from_str::<OpenAIErrorResponse>(data)
+        .ok()
+        .map(|response| response.error)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderMap;
+    use crate::UnilangError;
+    use super::{
+        parse_error_response,
+        OpenAIErrorKind,
+    };
+
+    #[test]
+    fn test_parse_error_response() {
+        let cases = [
+            (
+                401,
+                r#"{"error":{"message":"Incorrect API key provided: sk-xxx.","type":"invalid_request_error","param":null,"code":"invalid_api_key"}}"#,
+                OpenAIErrorKind::InvalidApiKey,
+            ),
+            (
+                429,
+                r#"{"error":{"message":"You exceeded your current quota, please check your plan and billing details.","type":"insufficient_quota","param":null,"code":"insufficient_quota"}}"#,
+                OpenAIErrorKind::InsufficientQuota,
+            ),
+            (
+                400,
+                r#"{"error":{"message":"This model's maximum context length is 4097 tokens.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+                OpenAIErrorKind::ContextLengthExceeded,
+            ),
+        ];
+
+        for (status, content, kind) in cases {
+            match parse_error_response(status, &HeaderMap::new(), content.to_string()) {
+                UnilangError::OpenAI(error) => {
+                    assert_eq!(error.kind(), kind);
+                    assert_eq!(error.status, Some(status));
+                },
+                error => panic!("unexpected error: {:?}", error),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_unknown_error_response() {
+        match parse_error_response(502, &HeaderMap::new(), "Bad Gateway".to_string()) {
+            UnilangError::HttpStatus { status, body, .. } => {
+                assert_eq!(status, 502);
+                assert_eq!(body, "Bad Gateway");
+            },
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+}
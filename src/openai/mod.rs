@@ -1,4 +1,8 @@
-mod auth;
-pub use auth::OPENAI_API_KEY;
+mod error;
+pub use error::{OpenAIError, OpenAIErrorKind};
+
+/// Base URL of OpenAI's API.
+pub const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
 
 pub mod chat;
+pub mod embedding;
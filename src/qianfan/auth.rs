@@ -1,37 +1,296 @@
-use anyhow::Result;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use reqwest::Client;
 use serde::Deserialize;
+use tokio::sync::Mutex;
 
-use crate::DOTENV_FILEPATH;
+use crate::{
+    Credentials,
+    UnilangError,
+    Result,
+};
 
 lazy_static::lazy_static! {
-    static ref QIANFAN_ACCESS_KEY: String = {
-        let _ = DOTENV_FILEPATH.as_ref();
-        dotenv::var("QIANFAN_ACCESS_KEY").unwrap()
-    };
-    static ref QIANFAN_SECRET_KEY: String = {
-        let _ = DOTENV_FILEPATH.as_ref();
-        dotenv::var("QIANFAN_SECRET_KEY").unwrap()
+    /// Access token caches of the accounts, keyed by base URLs and access keys.
+    static ref ACCESS_TOKEN_CACHES: std::sync::Mutex<HashMap<(String, String), Arc<AccessTokenCache>>> = {
+        std::sync::Mutex::new(HashMap::new())
     };
 }
 
-pub async fn get_access_token() -> Result<String> {
-    Ok(
-        reqwest::Client::new()
-            .post("https://aip.baidubce.com/oauth/2.0/token")
-            .query(&[
-                ("grant_type", "client_credentials"),
-                ("client_id", QIANFAN_ACCESS_KEY.as_str()),
-                ("client_secret", QIANFAN_SECRET_KEY.as_str()),
-            ])
-            .send()
-            .await?
-            .json::<TokenResponse>()
-            .await?
-            .access_token
-    )
+/// Refresh the access token this long before it expires.
+const ACCESS_TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// Get an access token, which is cached and shared by all requests of the same account
+/// until it is about to expire.
+/// 
+/// A new token is requested with `client` at `api_base`, which is `QIANFAN_API_BASE` unless a proxy is used.
+/// Tokens are not shared between base URLs, since a proxy may issue its own.
+pub async fn get_access_token(client: &Client, api_base: &str, credentials: &Credentials) -> Result<String> {
+    get_access_token_cache(api_base, credentials.qianfan_access_key()?)
+        .get(|| request_access_token(client, api_base, credentials))
+        .await
+}
+
+/// Replace the access token rejected by Qianfan with a new one.
+pub(crate) async fn refresh_access_token(
+    client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
+    rejected_access_token: &str,
+) -> Result<String> {
+    get_access_token_cache(api_base, credentials.qianfan_access_key()?)
+        .refresh(rejected_access_token, || request_access_token(client, api_base, credentials))
+        .await
+}
+
+/// Get the access token cache of the account at the base URL.
+fn get_access_token_cache(api_base: &str, access_key: &str) -> Arc<AccessTokenCache> {
+    ACCESS_TOKEN_CACHES
+        .lock()
+        .unwrap()
+        .entry((api_base.to_string(), access_key.to_string()))
+        .or_insert_with(|| Arc::new(AccessTokenCache::new()))
+        .clone()
+}
+
+/// Request a new access token from Qianfan's OAuth endpoint.
+async fn request_access_token(client: &Client, api_base: &str, credentials: &Credentials) -> Result<TokenResponse> {
+    let response_content = client
+        .post(format!("{}/oauth/2.0/token", api_base))
+        .query(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", credentials.qianfan_access_key()?),
+            ("client_secret", credentials.qianfan_secret_key()?),
+        ])
+        .send()
+        .await?
+        .text()
+        .await?;
+
+    // The OAuth endpoint responds with an error description
+    // if the access key or the secret key is invalid
+    match serde_json::from_str::<TokenResponse>(&response_content) {
+        Ok(response) => Ok(response),
+        Err(error) => match serde_json::from_str::<TokenErrorResponse>(&response_content) {
+            Ok(response) => Err(UnilangError::Auth(
+                format!("{} {}", response.error, response.error_description)
+            )),
+            Err(_) => Err(error.into()),
+        },
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct TokenResponse {
     access_token: String,
+
+    /// Lifetime of the access token in seconds.
+    expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenErrorResponse {
+    error: String,
+    error_description: String,
+}
+
+#[derive(Debug)]
+struct CachedAccessToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+impl CachedAccessToken {
+    fn new(response: TokenResponse) -> Self {
+        Self {
+            access_token: response.access_token,
+            expires_at: Instant::now() + Duration::from_secs(response.expires_in),
+        }
+    }
+
+    /// Check whether the access token is far enough from expiry to be used.
+    fn is_fresh(&self) -> bool {
+        Instant::now() + ACCESS_TOKEN_REFRESH_MARGIN < self.expires_at
+    }
+}
+
+/// A cache of one access token.
+/// 
+/// The lock is held while the token is being refreshed,
+/// so concurrent callers wait for the same refresh instead of starting their own.
+#[derive(Debug)]
+struct AccessTokenCache {
+    token: Mutex<Option<CachedAccessToken>>,
+}
+
+impl AccessTokenCache {
+    fn new() -> Self {
+        Self {
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Get the cached access token, or request a new one if it is missing or about to expire.
+    async fn get<F, Fut>(&self, request: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<TokenResponse>>,
+    {
+        let mut token = self.token.lock().await;
+
+        match token.as_ref() {
+            Some(cached_token) if cached_token.is_fresh() => Ok(cached_token.access_token.to_owned()),
+            _ => {
+                let cached_token = CachedAccessToken::new(request().await?);
+                let access_token = cached_token.access_token.to_owned();
+                *token = Some(cached_token);
+                Ok(access_token)
+            },
+        }
+    }
+
+    /// Request a new access token to replace the rejected one.
+    /// 
+    /// If another caller has already replaced it, then the new token is returned as is.
+    async fn refresh<F, Fut>(&self, rejected_access_token: &str, request: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<TokenResponse>>,
+    {
+        let mut token = self.token.lock().await;
+
+        match token.as_ref() {
+            Some(cached_token) if cached_token.access_token != rejected_access_token && cached_token.is_fresh() => {
+                Ok(cached_token.access_token.to_owned())
+            },
+            _ => {
+                let cached_token = CachedAccessToken::new(request().await?);
+                let access_token = cached_token.access_token.to_owned();
+                *token = Some(cached_token);
+                Ok(access_token)
+            },
+        }
+    }
+}
+
+/// Make the mock server grant the access token to every request for one.
+#[cfg(test)]
+pub(crate) async fn mock_access_token(server: &wiremock::MockServer, access_token: &str) {
+    use wiremock::{Mock, ResponseTemplate, matchers::path};
+
+    Mock::given(path("/oauth/2.0/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            format!(r#"{{"access_token":"{}","expires_in":2592000}}"#, access_token),
+            "application/json",
+        ))
+        .mount(server)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+    use anyhow::Result;
+    use reqwest::Client;
+    use wiremock::MockServer;
+    use crate::Credentials;
+    use super::{
+        AccessTokenCache,
+        TokenResponse,
+        get_access_token,
+        mock_access_token,
+    };
+
+    /// Create a request function that counts how many times it is called.
+    fn counting_request(
+        counter: Arc<AtomicUsize>,
+        expires_in: u64,
+    ) -> impl FnOnce() -> std::future::Ready<crate::Result<TokenResponse>> {
+        move || {
+            let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            std::future::ready(Ok(TokenResponse {
+                access_token: format!("token-{}", count),
+                expires_in,
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_callers_share_one_request() -> Result<()> {
+        let cache = Arc::new(AccessTokenCache::new());
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        let handles = (0..8).map(|_| {
+            let cache = cache.clone();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                cache.get(counting_request(counter, 2592000)).await
+            })
+        })
+        .collect::<Vec<_>>();
+
+        for handle in handles {
+            assert_eq!(handle.await??, "token-1");
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_before_expiry() -> Result<()> {
+        let cache = AccessTokenCache::new();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        // The token expires within the refresh margin, so it is requested again
+        assert_eq!(cache.get(counting_request(counter.clone(), 60)).await?, "token-1");
+        assert_eq!(cache.get(counting_request(counter.clone(), 60)).await?, "token-2");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rejected_token() -> Result<()> {
+        let cache = AccessTokenCache::new();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        assert_eq!(cache.get(counting_request(counter.clone(), 2592000)).await?, "token-1");
+
+        // The rejected token is replaced
+        assert_eq!(cache.refresh("token-1", counting_request(counter.clone(), 2592000)).await?, "token-2");
+
+        // A stale rejection does not replace the new token
+        assert_eq!(cache.refresh("token-1", counting_request(counter.clone(), 2592000)).await?, "token-2");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_per_api_base() -> Result<()> {
+        let client = Client::new();
+        let credentials = Credentials::builder()
+            .qianfan_access_key("access-key-per-api-base")
+            .qianfan_secret_key("secret-key")
+            .build();
+        let server = MockServer::start().await;
+        let proxy = MockServer::start().await;
+        mock_access_token(&server, "token-server").await;
+        mock_access_token(&proxy, "token-proxy").await;
+
+        // The same account gets the token issued at each base URL
+        assert_eq!(get_access_token(&client, &server.uri(), &credentials).await?, "token-server");
+        assert_eq!(get_access_token(&client, &proxy.uri(), &credentials).await?, "token-proxy");
+        assert_eq!(get_access_token(&client, &server.uri(), &credentials).await?, "token-server");
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        Ok(())
+    }
 }
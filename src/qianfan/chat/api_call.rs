@@ -1,7 +1,16 @@
-use anyhow::{Result, anyhow};
-use futures::Stream;
-use reqwest::Client;
-use super::super::get_access_token;
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt};
+use reqwest::{
+    Client,
+    header::CONTENT_TYPE,
+};
+use serde_json::{Map, Value};
+use crate::{Credentials, Result};
+use super::super::{
+    parse_error_response,
+    send_request,
+    send_with_access_token,
+};
 use super::QianfanChatModelName;
 use super::{
     QianfanChatRequestBody,
@@ -13,8 +22,12 @@ use super::{
 };
 
 /// Call Qianfan chat API and return a complete chat response.
+/// 
+/// The API is called at `api_base`, which is `QIANFAN_API_BASE` unless a proxy is used.
 pub async fn get_complete_chat_response(
     client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
     model_name: QianfanChatModelName,
     request_body: &QianfanChatRequestBody
 ) -> Result<QianfanChatResponse> {
@@ -29,35 +42,23 @@ pub async fn get_complete_chat_response(
     );
 
     // Call API to get chat response
-    let response = client
-        .post(get_api_endpoint(model_name)?)
-        .query(&[
-            ("access_token", get_access_token().await?.as_str()),
-        ])
-        .json(&request_body)
-        .send()
-        .await?;
-
-    // Get the response content
-    let response_content = response.text().await?;
-
-    // Parse the response content
-    // If the response is successful, parse the response content as QianfanChatResponse
-    // If the response is not successful, parse the response content as QianfanError
-    if let Ok(response) = serde_json::from_str::<QianfanChatResponse>(&response_content) {
-        Ok(response)
-    } else {
-        Err(
-            serde_json::from_str::<QianfanError>(&response_content)?.into()
-        )
-    }
+    let api_endpoint = &get_api_endpoint(api_base, model_name);
+    let request_body = &request_body;
+    send_with_access_token(client, api_base, credentials, |access_token| async move {
+        send_request(client, api_endpoint, request_body, &access_token).await
+    }).await
 }
 
 /// Call Qianfan chat API and return a stream of chat responses.
+/// 
+/// The API is called at `api_base`, which is `QIANFAN_API_BASE` unless a proxy is used.
 pub async fn get_streamed_chat_response(
     client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
+    model_name: QianfanChatModelName,
     request_body: &QianfanChatRequestBody
-) -> Result<impl Stream<Item = QianfanChatResponse>> {
+) -> Result<QianfanChatResponseStream<impl Stream<Item = Result<Bytes, reqwest::Error>> + Unpin>> {
     // Convert to a map
     let mut request_body = serde_json::to_value(request_body)?.as_object()
         .unwrap()
@@ -69,46 +70,110 @@ pub async fn get_streamed_chat_response(
     );
 
     // Call API to get chat response
-    let response = client
-        .post("https://aip.baidubce.com/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/eb-instant")
-        .query(&[
-            ("access_token", get_access_token().await?.as_str()),
-        ])
-        .json(&request_body)
-        .send()
-        .await?;
+    let api_endpoint = &get_api_endpoint(api_base, model_name);
+    let request_body = &request_body;
+    let bytes_stream = send_with_access_token(client, api_base, credentials, |access_token| async move {
+        send_streamed_chat_request(client, api_endpoint, request_body, &access_token).await
+    }).await?;
 
     // Create ChatResponseStream from the response bytes stream
     Ok(
-        QianfanChatResponseStream::new(response.bytes_stream())
+        QianfanChatResponseStream::new(bytes_stream)
     )
 }
 
-fn get_api_endpoint(model_name: QianfanChatModelName) -> Result<&'static str> {
-    match model_name {
-        QianfanChatModelName::ErnieBot4 => Ok("https://aip.baidubce.com/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions_pro"),
-        QianfanChatModelName::ErnieBot => Ok("https://aip.baidubce.com/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions"), 
-        QianfanChatModelName::ErnieBotTurbo => Ok("https://aip.baidubce.com/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/eb-instant"),
-        _ => Err(anyhow!("{:?} is not supported", model_name)),
+/// Send a chat request and return the bytes of the event stream.
+async fn send_streamed_chat_request(
+    client: &Client,
+    api_endpoint: &str,
+    request_body: &Map<String, Value>,
+    access_token: &str,
+) -> Result<impl Stream<Item = Result<Bytes, reqwest::Error>> + Unpin> {
+    let mut response = client
+        .post(api_endpoint)
+        .query(&[
+            ("access_token", access_token),
+        ])
+        .json(request_body)
+        .send()
+        .await?;
 
+    // Qianfan's gateway may reject the request with a body that is not a Qianfan error
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        return Err(parse_error_response(status.as_u16(), &headers, response.text().await?));
+    }
+
+    // Qianfan responds with a JSON error instead of the event stream
+    // if the request is rejected, not always with the JSON content type,
+    // so the first bytes of the body are checked too
+    let is_json_content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    let first_chunk = response.chunk().await?;
+    let is_json_body = first_chunk
+        .as_ref()
+        .and_then(|chunk| chunk.iter().find(|byte| !byte.is_ascii_whitespace()))
+        .is_some_and(|byte| *byte == b'{');
+
+    if is_json_content_type || is_json_body {
+        let mut content = first_chunk.map(Vec::from).unwrap_or_default();
+        content.extend_from_slice(&response.bytes().await?);
+        Err(serde_json::from_slice::<QianfanError>(&content)?.into())
+    } else {
+        Ok(stream::iter(first_chunk.map(Ok)).chain(response.bytes_stream()))
     }
 }
 
+/// Get the URL of the chat API of the model.
+fn get_api_endpoint(api_base: &str, model_name: QianfanChatModelName) -> String {
+    let path = match model_name {
+        QianfanChatModelName::ErnieBot4 => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions_pro",
+        QianfanChatModelName::ErnieBot => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions", 
+        QianfanChatModelName::ErnieBotTurbo => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/eb-instant",
+        QianfanChatModelName::Llama2Of7BChat => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/llama_2_7b",
+        QianfanChatModelName::Llama2Of13BChat => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/llama_2_13b",
+        QianfanChatModelName::Llama2Of70BChat => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/llama_2_70b",
+        QianfanChatModelName::QianfanChineseLlama2Of7B => "/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/qianfan_chinese_llama_2_7b",
+    };
+
+    format!("{}{}", api_base, path)
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use futures::stream::StreamExt;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::{method, path},
+    };
     use super::{
-        get_access_token,
         get_complete_chat_response,
         get_streamed_chat_response,
         QianfanChatModelName,
         QianfanChatRequestBody,
         
     };
-    use crate::qianfan::chat::{
-        QianfanChatMessage,
-        QianfanChatRole,
+    use crate::{
+        Credentials,
+        CredentialProvider,
+        EnvCredentialProvider,
+        UnilangError,
+        qianfan::{
+            QIANFAN_API_BASE,
+            get_access_token,
+            mock_access_token,
+            chat::{
+                QianfanChatMessage,
+                QianfanChatRole,
+            },
+        },
     };
 
     fn create_client() -> reqwest::Client {
@@ -122,17 +187,17 @@ mod tests {
     async fn test_get_complete_chat_response() -> Result<()> {
         // Create an HTTP client
         let client = create_client();
+        let credentials = EnvCredentialProvider.get_credentials().await?;
 
         // Call API to get chat response
         let response = get_complete_chat_response(
             &client,
+            QIANFAN_API_BASE,
+            &credentials,
             QianfanChatModelName::ErnieBotTurbo,
             &QianfanChatRequestBody::builder()
                 .messages(vec![
-                    QianfanChatMessage {
-                        role: QianfanChatRole::User,
-                        content: "What is Rust?".to_string(),
-                    },
+                    QianfanChatMessage::new(QianfanChatRole::User, "What is Rust?"),
                 ])
                 .temperature(0.9)
                 .build()
@@ -147,19 +212,20 @@ mod tests {
     async fn test_get_streamed_chat_response() -> Result<()> {
         // Create an HTTP client
         let client = create_client();
+        let credentials = EnvCredentialProvider.get_credentials().await?;
 
-        let access_token = get_access_token().await?;
+        let access_token = get_access_token(&client, QIANFAN_API_BASE, &credentials).await?;
         println!("access_token: {}", access_token);
 
         // Call API to get chat response
         let mut response = get_streamed_chat_response(
             &client,
+            QIANFAN_API_BASE,
+            &credentials,
+            QianfanChatModelName::ErnieBotTurbo,
             &QianfanChatRequestBody::builder()
                 .messages(vec![
-                    QianfanChatMessage {
-                        role: QianfanChatRole::User,
-                        content: "What is Rust?".to_string(),
-                    },
+                    QianfanChatMessage::new(QianfanChatRole::User, "What is Rust?"),
                 ])
                 .build()
         ).await?;
@@ -170,4 +236,90 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_get_streamed_chat_response_with_mock_server() -> Result<()> {
+        let content = concat!(
+            "data: {\"id\":\"as-1\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":0,\"is_end\":false,",
+            "\"is_truncated\":false,\"result\":\"你好，\",\"need_clear_history\":false,",
+            "\"usage\":{\"prompt_tokens\":2,\"completion_tokens\":0,\"total_tokens\":2}}\n\n",
+            "data: {\"id\":\"as-1\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":1,\"is_end\":true,",
+            "\"is_truncated\":false,\"result\":\"世界\",\"need_clear_history\":false,",
+            "\"usage\":{\"prompt_tokens\":2,\"completion_tokens\":4,\"total_tokens\":6}}\n\n",
+        ).as_bytes();
+
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-stream-pieces").await;
+        Mock::given(method("POST"))
+            .and(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/llama_2_13b"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(content, "text/event-stream"))
+            .mount(&server)
+            .await;
+        let credentials = Credentials::builder()
+            .qianfan_access_key("ak-stream-pieces")
+            .qianfan_secret_key("sk-stream-pieces")
+            .build();
+
+        let mut stream = get_streamed_chat_response(
+            &create_client(),
+            &server.uri(),
+            &credentials,
+            QianfanChatModelName::Llama2Of13BChat,
+            &QianfanChatRequestBody::builder()
+                .messages(vec![
+                    QianfanChatMessage::new(QianfanChatRole::User, "你好"),
+                ])
+                .build()
+        ).await?;
+
+        let mut results = vec![];
+        while let Some(response) = stream.next().await {
+            results.push(response?.result);
+        }
+
+        assert_eq!(results, vec!["你好，", "世界"]);
+        assert_eq!(stream.usage().map(|usage| usage.total_tokens), Some(6));
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[1].url.query(), Some("access_token=token-stream-pieces"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_streamed_error_body_with_mock_server() -> Result<()> {
+        // The error is sent as the body of an event stream
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-stream-error").await;
+        Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/eb-instant"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"error_code":336003,"error_msg":"the length of messages must be an odd number"}"#,
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        let credentials = Credentials::builder()
+            .qianfan_access_key("ak-stream-error")
+            .qianfan_secret_key("sk-stream-error")
+            .build();
+
+        let result = get_streamed_chat_response(
+            &create_client(),
+            &server.uri(),
+            &credentials,
+            QianfanChatModelName::ErnieBotTurbo,
+            &QianfanChatRequestBody::builder()
+                .messages(vec![
+                    QianfanChatMessage::new(QianfanChatRole::User, "What is Rust?"),
+                ])
+                .build()
+        ).await;
+
+        match result {
+            Err(UnilangError::Qianfan(error)) => assert_eq!(error.error_code, 336003),
+            Err(error) => panic!("unexpected error: {:?}", error),
+            Ok(_) => panic!("unexpected stream"),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use serde_with::skip_serializing_none;
+use super::QianfanChatMessage;
+
+/// A function that ERNIE-Bot may call.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize)]
+pub struct QianfanChatFunction {
+    pub name: String,
+    pub description: String,
+
+    /// Parameters of the function described as a JSON Schema object.
+    pub parameters: Value,
+
+    /// Response of the function described as a JSON Schema object.
+    pub responses: Option<Value>,
+
+    /// Example conversations in which the function is called.
+    pub examples: Option<Vec<Vec<QianfanChatMessage>>>,
+}
+
+/// A function call requested by ERNIE-Bot.
+#[skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QianfanFunctionCall {
+    pub name: String,
+
+    /// Arguments generated by the model in JSON, which may not be valid.
+    pub arguments: String,
+
+    /// The reasoning of the model behind the call.
+    pub thoughts: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        qianfan::mock_access_token,
+        qianfan::chat::{
+            get_complete_chat_response,
+            QianfanChatMessage,
+            QianfanChatModelName,
+            QianfanChatRequestBody,
+            QianfanChatRole,
+        },
+    };
+    use super::{
+        QianfanChatFunction,
+        QianfanFunctionCall,
+    };
+
+    fn get_weather_function() -> QianfanChatFunction {
+        QianfanChatFunction {
+            name: "get_weather".to_string(),
+            description: "Get the current weather of a city".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "city": { "type": "string" },
+                },
+                "required": ["city"],
+            }),
+            responses: None,
+            examples: Some(vec![vec![
+                QianfanChatMessage::new(QianfanChatRole::User, "What's the weather like in Beijing?"),
+                QianfanChatMessage::function_call(QianfanFunctionCall {
+                    name: "get_weather".to_string(),
+                    arguments: r#"{"city":"Beijing"}"#.to_string(),
+                    thoughts: None,
+                }),
+            ]]),
+        }
+    }
+
+    #[test]
+    fn test_serialize_functions() -> Result<()> {
+        let request_body = QianfanChatRequestBody::builder()
+            .messages(vec![
+                QianfanChatMessage::new(QianfanChatRole::User, "What's the weather like in Paris?"),
+            ])
+            .functions(vec![get_weather_function()])
+            .build();
+
+        assert_eq!(
+            serde_json::to_value(request_body)?["functions"],
+            json!([{
+                "name": "get_weather",
+                "description": "Get the current weather of a city",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "city": { "type": "string" },
+                    },
+                    "required": ["city"],
+                },
+                "examples": [[
+                    { "role": "user", "content": "What's the weather like in Beijing?" },
+                    {
+                        "role": "assistant",
+                        "content": "",
+                        "function_call": { "name": "get_weather", "arguments": "{\"city\":\"Beijing\"}" },
+                    },
+                ]],
+            }])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_function_call_with_mock_server() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-function-call").await;
+        for response in [
+            r#"{"id":"as-1","object":"chat.completion","created":1700000000,"result":"","is_truncated":false,"need_clear_history":false,"function_call":{"name":"get_weather","thoughts":"I need the weather of Paris","arguments":"{\"city\":\"Paris\"}"},"usage":{"prompt_tokens":60,"completion_tokens":20,"total_tokens":80}}"#,
+            r#"{"id":"as-2","object":"chat.completion","created":1700000001,"result":"It is sunny in Paris.","is_truncated":false,"need_clear_history":false,"usage":{"prompt_tokens":90,"completion_tokens":8,"total_tokens":98}}"#,
+        ] {
+            Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let credentials = Credentials::builder()
+            .qianfan_access_key("ak-function-call")
+            .qianfan_secret_key("sk-function-call")
+            .build();
+        let client = reqwest::Client::new();
+
+        // The model calls the function
+        let mut messages = vec![
+            QianfanChatMessage::new(QianfanChatRole::User, "What's the weather like in Paris?"),
+        ];
+        let response = get_complete_chat_response(
+            &client,
+            &server.uri(),
+            &credentials,
+            QianfanChatModelName::ErnieBot,
+            &QianfanChatRequestBody::builder()
+                .messages(messages.clone())
+                .functions(vec![get_weather_function()])
+                .build(),
+        ).await?;
+        let function_call = response.function_call.unwrap();
+        assert_eq!(function_call.name, "get_weather");
+        assert_eq!(function_call.arguments, r#"{"city":"Paris"}"#);
+
+        // Send the result of the function back
+        messages.push(QianfanChatMessage::function_call(function_call));
+        messages.push(QianfanChatMessage::function_result("get_weather", r#"{"weather":"sunny"}"#));
+        let response = get_complete_chat_response(
+            &client,
+            &server.uri(),
+            &credentials,
+            QianfanChatModelName::ErnieBot,
+            &QianfanChatRequestBody::builder()
+                .messages(messages)
+                .functions(vec![get_weather_function()])
+                .build(),
+        ).await?;
+        assert_eq!(response.result, "It is sunny in Paris.");
+
+        // Check what is sent to the server
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].url.query(), Some("access_token=token-function-call"));
+        assert_eq!(
+            requests[2].body_json::<serde_json::Value>()?["messages"],
+            json!([
+                { "role": "user", "content": "What's the weather like in Paris?" },
+                {
+                    "role": "assistant",
+                    "content": "",
+                    "function_call": {
+                        "name": "get_weather",
+                        "arguments": "{\"city\":\"Paris\"}",
+                        "thoughts": "I need the weather of Paris",
+                    },
+                },
+                { "role": "function", "content": "{\"weather\":\"sunny\"}", "name": "get_weather" },
+            ])
+        );
+
+        Ok(())
+    }
+}
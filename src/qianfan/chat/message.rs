@@ -1,17 +1,63 @@
 use serde::{Serialize, Deserialize};
+use serde_with::skip_serializing_none;
+use super::QianfanFunctionCall;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QianfanChatMessage {
     
     pub role: QianfanChatRole,
+
+    /// The content, which may be empty in assistant messages that call functions.
     pub content: String,
+
+    /// Name of the function whose result is the content of a function message.
+    pub name: Option<String>,
+
+    /// Function called by the assistant.
+    pub function_call: Option<QianfanFunctionCall>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl QianfanChatMessage {
+    /// Create a message with text content.
+    pub fn new<S: AsRef<str>>(role: QianfanChatRole, content: S) -> Self {
+        Self {
+            role,
+            content: content.as_ref().to_string(),
+            name: None,
+            function_call: None,
+        }
+    }
+
+    /// Create an assistant message that calls a function.
+    pub fn function_call(function_call: QianfanFunctionCall) -> Self {
+        Self {
+            role: QianfanChatRole::Assistant,
+            content: String::new(),
+            name: None,
+            function_call: Some(function_call),
+        }
+    }
+
+    /// Create a function message with the result of a function call.
+    pub fn function_result<S: AsRef<str>, T: AsRef<str>>(name: S, content: T) -> Self {
+        Self {
+            role: QianfanChatRole::Function,
+            content: content.as_ref().to_string(),
+            name: Some(name.as_ref().to_string()),
+            function_call: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QianfanChatRole {
     #[serde(rename = "user")]
     User,
 
     #[serde(rename = "assistant")]
     Assistant,
-}
\ No newline at end of file
+
+    #[serde(rename = "function")]
+    Function,
+}
@@ -6,6 +6,12 @@ pub use message::{
     QianfanChatRole,
 };
 
+mod function;
+pub use function::{
+    QianfanChatFunction,
+    QianfanFunctionCall,
+};
+
 mod request_body;
 pub use request_body::QianfanChatRequestBody;
 
@@ -19,6 +25,10 @@ mod model_names;
 pub use model_names::QianfanChatModelName;
 
 mod response;
-pub use response::QianfanChatResponse;
+pub use response::{
+    QianfanChatResponse,
+    QianfanChatResponseStream,
+    QianfanChatTokenUsage,
+};
 
 
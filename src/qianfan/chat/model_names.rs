@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum QianfanChatModelName {
     ErnieBot4,
     ErnieBot,
@@ -8,3 +8,18 @@ pub enum QianfanChatModelName {
     Llama2Of70BChat,
     QianfanChineseLlama2Of7B,
 }
+
+impl QianfanChatModelName {
+    /// Name of the model in Qianfan's documentation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QianfanChatModelName::ErnieBot4 => "ERNIE-Bot-4",
+            QianfanChatModelName::ErnieBot => "ERNIE-Bot",
+            QianfanChatModelName::ErnieBotTurbo => "ERNIE-Bot-turbo",
+            QianfanChatModelName::Llama2Of7BChat => "Llama-2-7b-chat",
+            QianfanChatModelName::Llama2Of13BChat => "Llama-2-13b-chat",
+            QianfanChatModelName::Llama2Of70BChat => "Llama-2-70b-chat",
+            QianfanChatModelName::QianfanChineseLlama2Of7B => "Qianfan-Chinese-Llama-2-7B",
+        }
+    }
+}
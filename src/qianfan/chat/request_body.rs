@@ -1,6 +1,9 @@
 use serde::Serialize;
 use serde_with::skip_serializing_none;
-use super::QianfanChatMessage;
+use super::{
+    QianfanChatMessage,
+    QianfanChatFunction,
+};
 
 #[skip_serializing_none]
 #[derive(Debug, Serialize)]
@@ -11,6 +14,7 @@ pub struct QianfanChatRequestBody {
     pub penalty_score: f32,
     pub system: Option<String>,
     pub user_id: Option<String>,
+    pub functions: Option<Vec<QianfanChatFunction>>,
 }
 
 impl QianfanChatRequestBody {
@@ -26,6 +30,7 @@ pub struct QianfanChatRequestBodyBuilder {
     penalty_score: f32,
     system: Option<String>,
     user_id: Option<String>,
+    functions: Option<Vec<QianfanChatFunction>>,
 }
 
 impl QianfanChatRequestBodyBuilder {
@@ -38,6 +43,7 @@ impl QianfanChatRequestBodyBuilder {
             penalty_score: 1.0,
             system: None,
             user_id: None,
+            functions: None,
         }
     }
 
@@ -71,6 +77,12 @@ impl QianfanChatRequestBodyBuilder {
         self
     }
 
+    /// Set functions that ERNIE-Bot may call.
+    pub fn functions(mut self, functions: Vec<QianfanChatFunction>) -> Self {
+        self.functions = Some(functions);
+        self
+    }
+
     pub fn build(self) -> QianfanChatRequestBody {
         QianfanChatRequestBody {
             messages: self.messages,
@@ -79,6 +91,7 @@ impl QianfanChatRequestBodyBuilder {
             penalty_score: self.penalty_score,
             system: self.system,
             user_id: self.user_id,
+            functions: self.functions,
         }
     }
 }
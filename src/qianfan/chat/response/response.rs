@@ -1,6 +1,9 @@
 use serde::Deserialize;
 use bytes::Bytes;
-use super::QianfanChatTokenUsage;
+use super::{
+    super::QianfanFunctionCall,
+    QianfanChatTokenUsage,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct QianfanChatResponse {
@@ -10,8 +13,16 @@ pub struct QianfanChatResponse {
     pub sentence_id: Option<u32>,
     pub is_end: Option<bool>,
     pub is_truncated: bool,
+
+    /// The generated content, which is empty if a function is called.
+    #[serde(default)]
     pub result: String,
+
     pub need_clear_history: bool,
+
+    /// Function called by ERNIE-Bot.
+    pub function_call: Option<QianfanFunctionCall>,
+
     pub usage: QianfanChatTokenUsage,
 }
 
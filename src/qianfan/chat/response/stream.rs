@@ -2,66 +2,28 @@ use std::{
     pin::Pin, 
     task::{Context, Poll}
 };
-use anyhow::Result;
 use futures::stream::{Stream, StreamExt};
-use regex::Regex;
-use lazy_static::lazy_static;
 use bytes::Bytes;
-use super::QianfanChatResponse;
-
-lazy_static! {
-    static ref STREAM_RESPONSE_CHUNK_RE: Regex = Regex::new(r"data: [^\n]+\n\n").unwrap();
-}
-
-pub fn extract_first_response(content: &str) -> Result<ExtractedResponseWithRemainingContent> {
-    if let Some(mat) = STREAM_RESPONSE_CHUNK_RE.find(content) {
-        // Matched string
-        let matched_str = mat.as_str();
-
-        // Remaining content
-        let remaining_content = &content[mat.end()..];
-
-        // Extract the JSON content
-        let json_content = matched_str.strip_prefix("data: ").unwrap();
-
-        // Parse the JSON content to QianfanChatResponse
-        let response = serde_json::from_str(json_content)?;
-
-        // Return the extracted response and remaining content
-        Ok(
-            ExtractedResponseWithRemainingContent {
-                response: Some(response),
-
-                // If there is no remaining content, return None
-                remaining_content: match remaining_content.len() {
-                    0 => None,
-                    _ => Some(remaining_content.to_string()),
-                },
-            }
-        )
-    } else {
-        // There is no response in the json_content,
-        // so return None for the response and the json_content as the remaining content
-        Ok(
-            ExtractedResponseWithRemainingContent {
-                response: None,
-                remaining_content: match content.len() {
-                    0 => None,
-                    _ => Some(content.to_string()),
-                },
-            }
-        )
-    }
-}
-
-pub struct ExtractedResponseWithRemainingContent {
-    pub response: Option<QianfanChatResponse>,
-    pub remaining_content: Option<String>,
-}
+use crate::{
+    SseStream,
+    UnilangError,
+    Result,
+    qianfan::QianfanError,
+};
+use super::{
+    QianfanChatResponse,
+    QianfanChatTokenUsage,
+};
 
+/// A stream of pieces of a chat response, which ends with the piece marked by `is_end`.
+/// 
+/// Transport errors, errors sent by Qianfan in the stream
+/// and the end of the stream before the last piece are yielded as errors,
+/// after which the stream ends.
 pub struct QianfanChatResponseStream<S> {
-    response_bytes_stream: S,
-    remaining_content: Option<String>,
+    event_stream: SseStream<S>,
+    is_terminated: bool,
+    usage: Option<QianfanChatTokenUsage>,
 }
 
 impl<S> QianfanChatResponseStream<S>
@@ -70,61 +32,142 @@ where
 {
     pub fn new(response_bytes_stream: S) -> Self {
         Self { 
-            response_bytes_stream, 
-            remaining_content: None,
+            event_stream: SseStream::new(response_bytes_stream),
+            is_terminated: false,
+            usage: None,
         }
     }
+
+    /// Get the token usage of the whole response, once the last piece has been received.
+    pub fn usage(&self) -> Option<&QianfanChatTokenUsage> {
+        self.usage.as_ref()
+    }
 }
 
 impl<S> Stream for QianfanChatResponseStream<S> 
 where 
     S: Stream<Item = Result<Bytes, reqwest::Error>> + Unpin
 {
-    type Item = QianfanChatResponse;
+    type Item = Result<QianfanChatResponse>;
 
     fn poll_next(
             mut self: Pin<&mut Self>, 
             cx: &mut Context<'_>
         ) -> Poll<Option<Self::Item>> {
-        
-        match futures::ready!(self.response_bytes_stream.poll_next_unpin(cx)) {
-            Some(Ok(bytes)) => {
-                // Convert bytes to string
-                let mut content = std::str::from_utf8(&bytes).unwrap().to_string();
-
-                // Concatenate the remaining content if there is any
-                if let Some(remaining_content) = &self.remaining_content {
-                    // Put the remaining content in front of the currently received content
-                    content.insert_str(0, remaining_content);
-                }
-
-                // Extract the first response and remaining content
-                let response_with_remaining_content = extract_first_response(&content).unwrap();
-
-                // Get the response and remaining content
-                let response = response_with_remaining_content.response;
-                let remaining_content = response_with_remaining_content.remaining_content;
-                
-                // Collect the remaining content if there is any
-                if let Some(remaining_content) = remaining_content {
-                    self.remaining_content = Some(remaining_content);
-                } else {
-                    self.remaining_content = None;
-                }
-
-                // Return Ready if there is one response
-                if let Some(response) = response {
-                    return Poll::Ready(Some(response));
-                } else {
-                    return Poll::Pending;
-                }
+        if self.is_terminated {
+            return Poll::Ready(None);
+        }
+
+        let item = match futures::ready!(self.event_stream.poll_next_unpin(cx)) {
+            Some(Ok(event)) => match serde_json::from_str::<QianfanChatResponse>(&event.data) {
+                Ok(response) => Some(Ok(response)),
+                Err(error) => match serde_json::from_str::<QianfanError>(&event.data) {
+                    Ok(qianfan_error) => Some(Err(qianfan_error.into())),
+                    Err(_) => Some(Err(error.into())),
+                },
             },
-            Some(Err(_)) => {
-                return Poll::Ready(None);
+            Some(Err(error)) => Some(Err(error.into())),
+            None => Some(Err(UnilangError::IncompleteStream(
+                "Qianfan closed the stream before the last piece of the response".to_string()
+            ))),
+        };
+
+        // Nothing is returned after the last piece or an error
+        match &item {
+            Some(Ok(response)) if response.is_end == Some(true) => {
+                self.usage = Some(response.usage.clone());
+                self.is_terminated = true;
             },
-            None => {
-                return Poll::Ready(None);
-            }
+            Some(Ok(_)) => (),
+            _ => self.is_terminated = true,
         }
+
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use bytes::Bytes;
+    use futures::{stream, Stream, StreamExt};
+    use crate::UnilangError;
+    use super::{
+        QianfanChatResponse,
+        QianfanChatResponseStream,
+    };
+
+    const CONTENT: &str = concat!(
+        "data: {\"id\":\"as-1\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":0,\"is_end\":false,",
+        "\"is_truncated\":false,\"result\":\"你好，\",\"need_clear_history\":false,",
+        "\"usage\":{\"prompt_tokens\":2,\"completion_tokens\":0,\"total_tokens\":2}}\n\n",
+        "data: {\"id\":\"as-1\",\"object\":\"chat.completion\",\"created\":1700000000,\"sentence_id\":1,\"is_end\":true,",
+        "\"is_truncated\":false,\"result\":\"世界\",\"need_clear_history\":false,",
+        "\"usage\":{\"prompt_tokens\":2,\"completion_tokens\":4,\"total_tokens\":6}}\n\n",
+    );
+
+    fn create_stream(content: &[u8], chunk_size: usize) -> impl Stream<Item = crate::Result<QianfanChatResponse>> {
+        QianfanChatResponseStream::new(stream::iter(
+            content
+                .chunks(chunk_size)
+                .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                .collect::<Vec<_>>()
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_split_chunks() -> Result<()> {
+        // Split within the Chinese characters and between the events
+        for chunk_size in 1..=8 {
+            let results = create_stream(CONTENT.as_bytes(), chunk_size)
+                .map(|response| response.map(|response| response.result))
+                .collect::<Vec<_>>()
+                .await
+                .into_iter()
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            assert_eq!(results, vec!["你好，", "世界"]);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_end_of_stream() -> Result<()> {
+        // Nothing is read after the last piece
+        let content = format!("{}data: {{\"unexpected\": true}}\n\n", CONTENT);
+        let mut stream = QianfanChatResponseStream::new(stream::iter(vec![
+            Ok(Bytes::from(content)),
+        ]));
+
+        assert!(stream.next().await.transpose()?.is_some());
+        assert!(stream.usage().is_none());
+        assert!(stream.next().await.transpose()?.is_some());
+        assert!(stream.next().await.is_none());
+        assert_eq!(stream.usage().map(|usage| usage.total_tokens), Some(6));
+
+        // Closed before the last piece
+        let content = CONTENT.split_inclusive("\n\n").next().unwrap();
+        let items = create_stream(content.as_bytes(), 16)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items.len(), 2);
+        assert!(items[0].is_ok());
+        assert!(matches!(items[1], Err(UnilangError::IncompleteStream(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_error() {
+        let content = "data: {\"error_code\":336100,\"error_msg\":\"try again later\"}\n\n";
+
+        let items = create_stream(content.as_bytes(), 16)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(items.len(), 1);
+        assert!(matches!(&items[0], Err(UnilangError::Qianfan(error)) if error.error_code == 336100));
     }
 }
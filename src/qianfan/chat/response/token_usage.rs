@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct QianfanChatTokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
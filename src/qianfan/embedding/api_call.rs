@@ -0,0 +1,101 @@
+use reqwest::Client;
+use crate::{Credentials, Result};
+use super::super::{
+    send_request,
+    send_with_access_token,
+};
+use super::{
+    QianfanEmbeddingModelName,
+    QianfanEmbeddingRequestBody,
+    QianfanEmbeddingResponse,
+};
+
+/// Call Qianfan embedding API and return the embeddings of the input.
+/// 
+/// The input must respect the limits of the model,
+/// see `QianfanEmbeddingModelName::max_inputs`, `QianfanEmbeddingModelName::max_input_chars`
+/// and `QianfanEmbeddingModelName::max_input_tokens`.
+/// The API is called at `api_base`, which is `QIANFAN_API_BASE` unless a proxy is used.
+pub async fn get_embeddings(
+    client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
+    model_name: QianfanEmbeddingModelName,
+    request_body: &QianfanEmbeddingRequestBody,
+) -> Result<QianfanEmbeddingResponse> {
+    // Call API to get embeddings
+    let api_endpoint = &get_api_endpoint(api_base, model_name);
+    send_with_access_token(client, api_base, credentials, |access_token| async move {
+        send_request(client, api_endpoint, request_body, &access_token).await
+    }).await
+}
+
+/// Get the URL of the embedding API of the model.
+fn get_api_endpoint(api_base: &str, model_name: QianfanEmbeddingModelName) -> String {
+    let path = match model_name {
+        QianfanEmbeddingModelName::EmbeddingV1 => "/rpc/2.0/ai_custom/v1/wenxinworkshop/embeddings/embedding-v1",
+        QianfanEmbeddingModelName::BgeLargeZh => "/rpc/2.0/ai_custom/v1/wenxinworkshop/embeddings/bge_large_zh",
+        QianfanEmbeddingModelName::BgeLargeEn => "/rpc/2.0/ai_custom/v1/wenxinworkshop/embeddings/bge_large_en",
+        QianfanEmbeddingModelName::Tao8K => "/rpc/2.0/ai_custom/v1/wenxinworkshop/embeddings/tao_8k",
+    };
+
+    format!("{}{}", api_base, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use reqwest::Client;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        UnilangError,
+        qianfan::mock_access_token,
+    };
+    use super::{
+        get_embeddings,
+        QianfanEmbeddingModelName,
+        QianfanEmbeddingRequestBody,
+    };
+
+    #[tokio::test]
+    async fn test_get_embeddings_with_mock_server() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-embedding").await;
+        for response in [
+            r#"{"id":"as-1","object":"embedding_list","created":1700000000,"data":[{"object":"embedding","embedding":[0.5,-0.25],"index":0}],"usage":{"prompt_tokens":3,"total_tokens":3}}"#,
+            r#"{"error_code":336003,"error_msg":"embeddings max tokens per batch size is 384"}"#,
+        ] {
+            Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/embeddings/bge_large_zh"))
+                .respond_with(ResponseTemplate::new(200).set_body_raw(response, "application/json"))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let credentials = Credentials::builder()
+            .qianfan_access_key("ak-embedding")
+            .qianfan_secret_key("sk-embedding")
+            .build();
+        let request_body = QianfanEmbeddingRequestBody::new(vec!["什么是 Rust？".to_string()]);
+
+        let response = get_embeddings(&Client::new(), &server.uri(), &credentials, QianfanEmbeddingModelName::BgeLargeZh, &request_body).await?;
+        assert_eq!(response.data[0].embedding, vec![0.5, -0.25]);
+        assert_eq!(response.usage.total_tokens, 3);
+
+        match get_embeddings(&Client::new(), &server.uri(), &credentials, QianfanEmbeddingModelName::BgeLargeZh, &request_body).await {
+            Err(UnilangError::Qianfan(error)) => assert_eq!(error.error_code, 336003),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[1].url.query(), Some("access_token=token-embedding"));
+        assert_eq!(requests[1].body_json::<serde_json::Value>()?, serde_json::json!({"input": ["什么是 Rust？"]}));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+pub use super::QianfanError;
+
+mod model_names;
+pub use model_names::QianfanEmbeddingModelName;
+
+mod request_body;
+pub use request_body::QianfanEmbeddingRequestBody;
+
+mod response;
+pub use response::{
+    QianfanEmbeddingResponse,
+    QianfanEmbedding,
+    QianfanEmbeddingTokenUsage,
+};
+
+mod api_call;
+pub use api_call::get_embeddings;
@@ -0,0 +1,35 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QianfanEmbeddingModelName {
+    EmbeddingV1,
+    BgeLargeZh,
+    BgeLargeEn,
+    Tao8K,
+}
+
+impl QianfanEmbeddingModelName {
+    /// Maximum number of inputs in a request.
+    pub fn max_inputs(&self) -> usize {
+        match self {
+            Self::EmbeddingV1 | Self::BgeLargeZh | Self::BgeLargeEn => 16,
+            Self::Tao8K => 1,
+        }
+    }
+
+    /// Maximum number of characters in an input.
+    pub fn max_input_chars(&self) -> usize {
+        match self {
+            Self::EmbeddingV1 => 1000,
+            Self::BgeLargeZh | Self::BgeLargeEn => 2000,
+            Self::Tao8K => 28000,
+        }
+    }
+
+    /// Maximum number of tokens in an input.
+    pub fn max_input_tokens(&self) -> u32 {
+        match self {
+            Self::EmbeddingV1 => 384,
+            Self::BgeLargeZh | Self::BgeLargeEn => 512,
+            Self::Tao8K => 8192,
+        }
+    }
+}
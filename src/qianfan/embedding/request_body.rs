@@ -0,0 +1,18 @@
+use serde::Serialize;
+use serde_with::skip_serializing_none;
+
+#[skip_serializing_none]
+#[derive(Debug, Serialize)]
+pub struct QianfanEmbeddingRequestBody {
+    pub input: Vec<String>,
+    pub user_id: Option<String>,
+}
+
+impl QianfanEmbeddingRequestBody {
+    pub fn new(input: Vec<String>) -> Self {
+        Self {
+            input,
+            user_id: None,
+        }
+    }
+}
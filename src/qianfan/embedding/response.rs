@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct QianfanEmbeddingResponse {
+    pub id: String,
+    pub object: String,
+    pub created: u32,
+    pub data: Vec<QianfanEmbedding>,
+    pub usage: QianfanEmbeddingTokenUsage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QianfanEmbedding {
+    pub object: String,
+    pub embedding: Vec<f32>,
+    pub index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QianfanEmbeddingTokenUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
@@ -1,6 +1,10 @@
+use reqwest::header::HeaderMap;
 use thiserror::Error;
 use serde::Deserialize;
-// use anyhow::Error;
+use crate::{
+    UnilangError,
+    retry::parse_retry_after,
+};
 
 #[derive(Debug, Error, Deserialize)]
 #[error("QianfanError: {error_code} {error_msg}")]
@@ -8,3 +12,30 @@ pub struct QianfanError {
     pub error_code: u32,
     pub error_msg: String,
 }
+
+impl QianfanError {
+    /// Check whether the error is caused by an invalid or expired access token.
+    pub fn is_access_token_invalid(&self) -> bool {
+        matches!(self.error_code, 110 | 111)
+    }
+
+    /// Check whether the error is temporary, such as an exceeded rate limit or an internal error.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.error_code, 2 | 18 | 336100 | 336501 | 336502)
+    }
+}
+
+/// Convert the content of an unsuccessful response to an error.
+/// 
+/// If the content is not a Qianfan error,
+/// then the HTTP status and the raw content are returned instead.
+pub(crate) fn parse_error_response(status: u16, headers: &HeaderMap, response_content: String) -> UnilangError {
+    match serde_json::from_str::<QianfanError>(&response_content) {
+        Ok(error) => error.into(),
+        Err(_) => UnilangError::HttpStatus {
+            status,
+            body: response_content,
+            retry_after: parse_retry_after(headers),
+        },
+    }
+}
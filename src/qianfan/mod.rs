@@ -1,8 +1,20 @@
 mod error;
 pub use error::QianfanError;
+use error::parse_error_response;
+
+/// Base URL of Qianfan's API.
+pub const QIANFAN_API_BASE: &str = "https://aip.baidubce.com";
 
 mod auth;
 pub use auth::get_access_token;
+use auth::refresh_access_token;
+#[cfg(test)]
+pub(crate) use auth::mock_access_token;
+
+mod request;
+use request::{send_request, send_with_access_token};
 
 pub mod chat;
+pub mod embedding;
+pub mod tokenizer;
 
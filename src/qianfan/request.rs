@@ -0,0 +1,71 @@
+use std::future::Future;
+use reqwest::Client;
+use serde::{Serialize, de::DeserializeOwned};
+use crate::{Credentials, UnilangError, Result};
+use super::{
+    get_access_token,
+    refresh_access_token,
+    parse_error_response,
+    QianfanError,
+};
+
+/// Send a request with the access token of the account.
+///
+/// If the access token is rejected, then the request is sent once more with a new one.
+pub(crate) async fn send_with_access_token<T, F, Fut>(
+    client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
+    send: F,
+) -> Result<T>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let access_token = get_access_token(client, api_base, credentials).await?;
+    match send(access_token.clone()).await {
+        Err(UnilangError::Qianfan(error)) if error.is_access_token_invalid() => {
+            let access_token = refresh_access_token(client, api_base, credentials, &access_token).await?;
+            send(access_token).await
+        },
+        result => result,
+    }
+}
+
+/// Send a request to an API endpoint and parse the response.
+pub(crate) async fn send_request<B: Serialize + ?Sized, T: DeserializeOwned>(
+    client: &Client,
+    api_endpoint: &str,
+    request_body: &B,
+    access_token: &str,
+) -> Result<T> {
+    let response = client
+        .post(api_endpoint)
+        .query(&[
+            ("access_token", access_token),
+        ])
+        .json(request_body)
+        .send()
+        .await?;
+
+    // Qianfan's gateway may reject the request with a body that is not a Qianfan error
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        return Err(parse_error_response(status.as_u16(), &headers, response.text().await?));
+    }
+
+    // Get the response content
+    let response_content = response.text().await?;
+
+    // Parse the response content
+    // If the response is successful, parse the response content as T
+    // If the response is not successful, parse the response content as QianfanError
+    match serde_json::from_str::<T>(&response_content) {
+        Ok(response) => Ok(response),
+        Err(error) => match serde_json::from_str::<QianfanError>(&response_content) {
+            Ok(qianfan_error) => Err(qianfan_error.into()),
+            Err(_) => Err(error.into()),
+        },
+    }
+}
@@ -0,0 +1,82 @@
+use reqwest::Client;
+use crate::{Credentials, Result};
+use super::super::{
+    send_request,
+    send_with_access_token,
+};
+use super::{
+    QianfanTokenizerRequestBody,
+    QianfanTokenizerResponse,
+};
+
+/// Call Qianfan tokenizer API and return the number of tokens of the prompt.
+/// 
+/// The API is called at `api_base`, which is `QIANFAN_API_BASE` unless a proxy is used.
+pub async fn count_tokens(
+    client: &Client,
+    api_base: &str,
+    credentials: &Credentials,
+    request_body: &QianfanTokenizerRequestBody,
+) -> Result<QianfanTokenizerResponse> {
+    // Call API to count tokens
+    let api_endpoint = &format!(
+        "{}/rpc/2.0/ai_custom/v1/wenxinworkshop/tokenizer/erniebot",
+        api_base,
+    );
+    send_with_access_token(client, api_base, credentials, |access_token| async move {
+        send_request(client, api_endpoint, request_body, &access_token).await
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use reqwest::Client;
+    use serde_json::json;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::{method, path},
+    };
+    use crate::{
+        Credentials,
+        qianfan::mock_access_token,
+    };
+    use super::{
+        count_tokens,
+        QianfanTokenizerRequestBody,
+    };
+
+    #[tokio::test]
+    async fn test_count_tokens_with_mock_server() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-tokenizer").await;
+        Mock::given(method("POST"))
+            .and(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/tokenizer/erniebot"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"object":"tokenizer","created":1700000000,"usage":{"prompt_tokens":6,"total_tokens":6}}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let credentials = Credentials::builder()
+            .qianfan_access_key("ak-tokenizer")
+            .qianfan_secret_key("sk-tokenizer")
+            .build();
+
+        let response = count_tokens(
+            &Client::new(),
+            &server.uri(),
+            &credentials,
+            &QianfanTokenizerRequestBody::new("Rust 是什么？"),
+        ).await?;
+        assert_eq!(response.usage.prompt_tokens, 6);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[1].url.query(), Some("access_token=token-tokenizer"));
+        assert_eq!(requests[1].body_json::<serde_json::Value>()?, json!({"prompt": "Rust 是什么？", "model": "ernie-bot"}));
+
+        Ok(())
+    }
+}
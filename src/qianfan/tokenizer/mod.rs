@@ -0,0 +1,13 @@
+pub use super::QianfanError;
+
+mod request_body;
+pub use request_body::QianfanTokenizerRequestBody;
+
+mod response;
+pub use response::{
+    QianfanTokenizerResponse,
+    QianfanTokenizerUsage,
+};
+
+mod api_call;
+pub use api_call::count_tokens;
@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct QianfanTokenizerRequestBody {
+    pub prompt: String,
+
+    /// Name of the model whose tokenizer is used, which is `ernie-bot` for ERNIE-Bot models.
+    pub model: String,
+}
+
+impl QianfanTokenizerRequestBody {
+    pub fn new<S: AsRef<str>>(prompt: S) -> Self {
+        Self {
+            prompt: prompt.as_ref().to_string(),
+            model: "ernie-bot".to_string(),
+        }
+    }
+}
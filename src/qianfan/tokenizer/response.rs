@@ -0,0 +1,14 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct QianfanTokenizerResponse {
+    pub object: String,
+    pub created: u32,
+    pub usage: QianfanTokenizerUsage,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QianfanTokenizerUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+use crate::{UnilangError, Result};
+
+/// A client-side limiter of requests and tokens,
+/// which holds requests back until they fit in the limits of the provider.
+///
+/// The limits usually apply to a provider account,
+/// so share one limiter through an `Arc` among all the models of the account.
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    requests_per_second: Option<u32>,
+    state: Mutex<RateLimiterState>,
+}
+
+#[derive(Debug, Default)]
+struct RateLimiterState {
+    /// Requests sent within the last minute, from the oldest.
+    requests: VecDeque<SentRequest>,
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct SentRequest {
+    id: u64,
+    sent_at: Instant,
+    tokens: u32,
+}
+
+const MINUTE: Duration = Duration::from_secs(60);
+const SECOND: Duration = Duration::from_secs(1);
+
+impl RateLimiter {
+    /// Create a builder for the rate limiter.
+    pub fn builder() -> RateLimiterBuilder {
+        RateLimiterBuilder::new()
+    }
+
+    /// Wait until a request with the estimated tokens fits in the limits, and record it.
+    ///
+    /// A request with more tokens than the limit per minute is let through once no other tokens are counted,
+    /// so that it fails at the provider instead of waiting forever.
+    pub async fn acquire(self: &Arc<Self>, estimated_tokens: u32) -> RateLimitPermit {
+        loop {
+            let delay = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+
+                // Forget the requests sent more than a minute ago
+                while state.requests.front().is_some_and(|request| now.duration_since(request.sent_at) >= MINUTE) {
+                    state.requests.pop_front();
+                }
+
+                match self.delay(&state, now, estimated_tokens) {
+                    Some(delay) => delay,
+                    None => {
+                        let id = state.next_id;
+                        state.next_id += 1;
+                        state.requests.push_back(SentRequest {
+                            id,
+                            sent_at: now,
+                            tokens: estimated_tokens,
+                        });
+
+                        return RateLimitPermit {
+                            limiter: self.clone(),
+                            id,
+                        };
+                    },
+                }
+            };
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Get how long to wait before the request fits, or `None` if it fits now.
+    fn delay(&self, state: &RateLimiterState, now: Instant, tokens: u32) -> Option<Duration> {
+        let mut delays = vec![];
+        let until_expired = |request: &SentRequest, window: Duration| {
+            (request.sent_at + window).saturating_duration_since(now)
+        };
+
+        if let Some(limit) = self.requests_per_minute {
+            let count = state.requests.len();
+            if count >= limit as usize {
+                delays.push(until_expired(&state.requests[count - limit as usize], MINUTE));
+            }
+        }
+
+        if let Some(limit) = self.requests_per_second {
+            let recent = state.requests
+                .iter()
+                .filter(|request| now.duration_since(request.sent_at) < SECOND)
+                .collect::<Vec<&SentRequest>>();
+            if recent.len() >= limit as usize {
+                delays.push(until_expired(recent[recent.len() - limit as usize], SECOND));
+            }
+        }
+
+        if let Some(limit) = self.tokens_per_minute {
+            // Wait for the oldest requests to expire until the tokens fit
+            let mut used_tokens = state.requests.iter().map(|request| request.tokens).sum::<u32>();
+            let mut requests = state.requests.iter();
+            while used_tokens > 0 && used_tokens.saturating_add(tokens) > limit {
+                let request = requests.next().unwrap();
+                used_tokens -= request.tokens;
+                if used_tokens == 0 || used_tokens.saturating_add(tokens) <= limit {
+                    delays.push(until_expired(request, MINUTE));
+                }
+            }
+        }
+
+        delays.into_iter().max()
+    }
+
+    /// Replace the estimated tokens of a request with its real usage.
+    fn record_usage(&self, id: u64, tokens: u32) {
+        let mut state = self.state.lock().unwrap();
+
+        // The request may have expired already
+        if let Some(request) = state.requests.iter_mut().find(|request| request.id == id) {
+            request.tokens = tokens;
+        }
+    }
+}
+
+/// A request let through by a `RateLimiter`.
+#[derive(Debug)]
+pub struct RateLimitPermit {
+    limiter: Arc<RateLimiter>,
+    id: u64,
+}
+
+impl RateLimitPermit {
+    /// Correct the tokens of the request with the usage reported by the provider.
+    pub fn record_usage(&self, tokens: u32) {
+        self.limiter.record_usage(self.id, tokens);
+    }
+}
+
+pub struct RateLimiterBuilder {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    requests_per_second: Option<u32>,
+}
+
+impl Default for RateLimiterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiterBuilder {
+    pub fn new() -> Self {
+        Self {
+            requests_per_minute: None,
+            tokens_per_minute: None,
+            requests_per_second: None,
+        }
+    }
+
+    /// Set the maximum number of requests per minute, i.e. OpenAI's RPM.
+    pub fn requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = Some(requests_per_minute);
+        self
+    }
+
+    /// Set the maximum number of tokens per minute, i.e. OpenAI's TPM.
+    pub fn tokens_per_minute(mut self, tokens_per_minute: u32) -> Self {
+        self.tokens_per_minute = Some(tokens_per_minute);
+        self
+    }
+
+    /// Set the maximum number of requests per second, i.e. Qianfan's QPS.
+    pub fn requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    /// Build the rate limiter, ready to be shared.
+    ///
+    /// An error is returned if a limit is 0, since no request could ever be sent.
+    pub fn build(self) -> Result<Arc<RateLimiter>> {
+        for (name, limit) in [
+            ("requests per minute", self.requests_per_minute),
+            ("tokens per minute", self.tokens_per_minute),
+            ("requests per second", self.requests_per_second),
+        ] {
+            if limit == Some(0) {
+                return Err(UnilangError::InvalidInput(
+                    format!("the {} must be positive", name)
+                ));
+            }
+        }
+
+        Ok(Arc::new(RateLimiter {
+            requests_per_minute: self.requests_per_minute,
+            tokens_per_minute: self.tokens_per_minute,
+            requests_per_second: self.requests_per_second,
+            state: Mutex::new(RateLimiterState::default()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use anyhow::Result;
+    use tokio::time::Instant;
+    use crate::UnilangError;
+    use super::RateLimiter;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_minute() -> Result<()> {
+        let limiter = RateLimiter::builder()
+            .requests_per_minute(2)
+            .build()?;
+        let start = Instant::now();
+
+        limiter.acquire(0).await;
+        limiter.acquire(0).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire(0).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(60));
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_second() -> Result<()> {
+        let limiter = RateLimiter::builder()
+            .requests_per_second(2)
+            .build()?;
+        let start = Instant::now();
+
+        for _ in 0..5 {
+            limiter.acquire(0).await;
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_zero_limits() {
+        for builder in [
+            RateLimiter::builder().requests_per_minute(0),
+            RateLimiter::builder().tokens_per_minute(0),
+            RateLimiter::builder().requests_per_second(0),
+        ] {
+            assert!(matches!(builder.build(), Err(UnilangError::InvalidInput(_))));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_per_minute() -> Result<()> {
+        let limiter = RateLimiter::builder()
+            .tokens_per_minute(100)
+            .build()?;
+        let start = Instant::now();
+
+        // The estimate is corrected with the real usage
+        let permit = limiter.acquire(80).await;
+        permit.record_usage(10);
+        limiter.acquire(80).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Waits until the first request expires
+        tokio::time::advance(Duration::from_secs(10)).await;
+        limiter.acquire(20).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(60));
+
+        // A request larger than the limit waits for all the others
+        limiter.acquire(150).await;
+        assert_eq!(start.elapsed(), Duration::from_secs(120));
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shared_limiter() -> Result<()> {
+        let limiter = RateLimiter::builder()
+            .requests_per_second(1)
+            .build()?;
+        let start = Instant::now();
+
+        let tasks = (0..3)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire(0).await;
+                })
+            })
+            .collect::<Vec<_>>();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+
+        Ok(())
+    }
+}
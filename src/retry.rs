@@ -0,0 +1,342 @@
+use std::{
+    future::Future,
+    time::Duration,
+};
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use tracing::warn;
+use crate::{UnilangError, Result};
+
+/// How failed requests are sent again.
+///
+/// Only errors that may go away by themselves are retried,
+/// such as rate limits, server errors and timeouts, see `UnilangError::is_retryable`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+
+    /// Maximum delay before a retry.
+    /// If the server asks to wait longer than this, the error is returned instead.
+    pub max_backoff: Duration,
+
+    /// Factor by which the delay grows after each retry.
+    pub multiplier: f64,
+
+    /// Whether the delay is randomly shortened by up to half,
+    /// so that clients that failed together do not retry together.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a builder for the retry policy.
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::new()
+    }
+
+    /// Create a policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Get the delay before the given retry, starting from 1.
+    ///
+    /// The delay is capped by the maximum backoff before it is converted to a `Duration`,
+    /// so that it cannot overflow however many retries there are.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let backoff = Duration::try_from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+            .unwrap_or(self.max_backoff);
+
+        match self.jitter {
+            true => backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0)),
+            false => backoff,
+        }
+    }
+
+    /// Run the operation until it succeeds, fails with an error that is not retryable,
+    /// or runs out of attempts.
+    ///
+    /// The delay that the server asks for with `Retry-After` takes precedence over the backoff.
+    pub async fn retry<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let error = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            if attempt >= self.max_attempts || !error.is_retryable() {
+                return Err(error);
+            }
+
+            let delay = match error.retry_after() {
+                Some(retry_after) if retry_after > self.max_backoff => return Err(error),
+                Some(retry_after) => retry_after,
+                None => self.backoff(attempt),
+            };
+
+            warn!("Retrying in {:?} after attempt {} failed: {}", delay, attempt, error);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+pub struct RetryPolicyBuilder {
+    policy: RetryPolicy,
+}
+
+impl Default for RetryPolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicyBuilder {
+    pub fn new() -> Self {
+        Self {
+            policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Set the maximum number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.policy.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.policy.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Set the maximum delay before a retry.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.policy.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the factor by which the delay grows after each retry.
+    /// It must be a finite number of at least 1.0, or `build` returns an error.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.policy.multiplier = multiplier;
+        self
+    }
+
+    /// Set whether the delay is randomly shortened.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.policy.jitter = jitter;
+        self
+    }
+
+    /// Build the retry policy.
+    ///
+    /// An error is returned if the multiplier is not finite or would shrink the delay.
+    pub fn build(self) -> Result<RetryPolicy> {
+        if !self.policy.multiplier.is_finite() || self.policy.multiplier < 1.0 {
+            return Err(UnilangError::InvalidInput(format!(
+                "the backoff multiplier must be a finite number of at least 1.0, but {} is given",
+                self.policy.multiplier,
+            )));
+        }
+
+        Ok(self.policy)
+    }
+}
+
+/// Get the delay that the server asks for in the headers of a response.
+///
+/// OpenAI's `retry-after-ms` is preferred over `Retry-After` in seconds.
+/// HTTP dates in `Retry-After` are not supported.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let get_seconds = |name: &str, scale: f64| {
+        headers
+            .get(name)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .map(|value| Duration::from_secs_f64(value / scale))
+    };
+
+    get_seconds("retry-after-ms", 1000.0).or_else(|| get_seconds("retry-after", 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicU32, Ordering},
+        time::Duration,
+    };
+    use anyhow::Result;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use crate::{
+        UnilangError,
+        qianfan::QianfanError,
+    };
+    use super::{
+        parse_retry_after,
+        RetryPolicy,
+    };
+
+    fn create_policy() -> RetryPolicy {
+        RetryPolicy::builder()
+            .max_attempts(3)
+            .initial_backoff(Duration::from_millis(1))
+            .jitter(false)
+            .build()
+            .unwrap()
+    }
+
+    fn create_error(error_code: u32) -> UnilangError {
+        QianfanError {
+            error_code,
+            error_msg: "error".to_string(),
+        }.into()
+    }
+
+    #[test]
+    fn test_backoff() -> Result<()> {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(5))
+            .jitter(false)
+            .build()?;
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for _ in 0..100 {
+            let backoff = policy.backoff(2);
+            assert!(backoff >= Duration::from_secs(1) && backoff <= Duration::from_secs(2));
+        }
+
+        // The delay is capped instead of overflowing
+        let policy = RetryPolicy {
+            initial_backoff: Duration::MAX,
+            multiplier: 1e300,
+            jitter: false,
+            ..policy
+        };
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(5));
+        let policy = RetryPolicy {
+            multiplier: f64::INFINITY,
+            ..policy
+        };
+        assert_eq!(policy.backoff(2), Duration::from_secs(5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_multiplier() {
+        for multiplier in [0.5, -2.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                RetryPolicy::builder().multiplier(multiplier).build(),
+                Err(UnilangError::InvalidInput(_))
+            ));
+        }
+        assert!(RetryPolicy::builder().multiplier(1.0).build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() -> Result<()> {
+        let attempts = AtomicU32::new(0);
+
+        let value = create_policy().retry(|| async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(create_error(18)),
+                1 => Err(create_error(336100)),
+                attempt => Ok(attempt),
+            }
+        }).await?;
+
+        assert_eq!(value, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_give_up() {
+        // Out of attempts
+        let attempts = AtomicU32::new(0);
+        let result: crate::Result<()> = create_policy().retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(create_error(18))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // Not retryable
+        let attempts = AtomicU32::new(0);
+        let result: crate::Result<()> = create_policy().retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(create_error(336003))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // Asked to wait longer than the maximum backoff
+        let attempts = AtomicU32::new(0);
+        let result: crate::Result<()> = create_policy().retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(UnilangError::HttpStatus {
+                status: 429,
+                body: String::new(),
+                retry_after: Some(Duration::from_secs(60)),
+            })
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(2)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("250"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_millis(250)));
+
+        headers.insert("retry-after-ms", HeaderValue::from_static("soon"));
+        headers.insert("retry-after", HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+}
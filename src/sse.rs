@@ -0,0 +1,334 @@
+//! A decoder of Server-Sent Events, following the event stream format of the HTML standard.
+//!
+//! See <https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation>
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+
+/// An event dispatched from an event stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// Type of the event, which is "message" unless the `event` field is set.
+    pub event: String,
+
+    /// Data of the event, with the lines of multiple `data` fields joined by "\n".
+    pub data: String,
+
+    /// The last event ID seen in the stream, if any.
+    pub id: Option<String>,
+
+    /// Reconnection time that the server asks for with this event, if any.
+    pub retry: Option<Duration>,
+}
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// An incremental decoder of an event stream.
+///
+/// The bytes may be split anywhere, even within a line or a UTF-8 character.
+/// Lines are only decoded once they are complete,
+/// and line endings are ASCII bytes that never appear within a multi-byte character.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// Bytes of the incomplete line.
+    line: Vec<u8>,
+
+    /// Whether the last byte was a "\r", whose "\n" may come with the next bytes.
+    after_cr: bool,
+
+    /// Whether the stream has started, so that a leading BOM is only stripped once.
+    started: bool,
+
+    event: String,
+    data: String,
+    last_event_id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the next bytes of the stream, and return the events completed by them.
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        let mut events = vec![];
+
+        // Strip the BOM at the start of the stream, which may arrive in pieces too
+        let bytes = match self.started {
+            true => bytes.to_vec(),
+            false => {
+                self.line.extend_from_slice(bytes);
+                if BOM.starts_with(&self.line) && self.line.len() < BOM.len() {
+                    return events;
+                }
+                self.started = true;
+                let bytes = std::mem::take(&mut self.line);
+                bytes.strip_prefix(BOM).map(<[u8]>::to_vec).unwrap_or(bytes)
+            },
+        };
+
+        for byte in bytes {
+            match byte {
+                // The "\n" of "\r\n"
+                b'\n' if self.after_cr => self.after_cr = false,
+                b'\r' | b'\n' => {
+                    self.after_cr = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+                },
+                _ => {
+                    self.after_cr = false;
+                    self.line.push(byte);
+                },
+            }
+        }
+
+        events
+    }
+
+    /// Process a complete line, and return the event if the line dispatches one.
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        // Invalid UTF-8 is replaced rather than rejected
+        let line = String::from_utf8_lossy(line);
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // Comments, i.e. keep-alive messages
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line.as_ref(), ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            },
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_digit()) => {
+                if let Ok(milliseconds) = value.parse() {
+                    self.retry = Some(Duration::from_millis(milliseconds));
+                }
+            },
+            // Unknown fields are ignored
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Dispatch the buffered event, unless it has no data.
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        let mut data = std::mem::take(&mut self.data);
+        let retry = self.retry.take();
+
+        if data.is_empty() {
+            return None;
+        }
+        data.pop();
+
+        Some(SseEvent {
+            event: match event.is_empty() {
+                true => "message".to_string(),
+                false => event,
+            },
+            data,
+            id: self.last_event_id.clone(),
+            retry,
+        })
+    }
+}
+
+/// A stream of events decoded from a stream of bytes, such as `reqwest::Response::bytes_stream`.
+///
+/// An event that is not ended by a blank line when the bytes end is discarded, as the standard requires.
+pub struct SseStream<S> {
+    bytes_stream: S,
+    decoder: SseDecoder,
+    events: VecDeque<SseEvent>,
+    is_finished: bool,
+}
+
+impl<S> SseStream<S> {
+    pub fn new(bytes_stream: S) -> Self {
+        Self {
+            bytes_stream,
+            decoder: SseDecoder::new(),
+            events: VecDeque::new(),
+            is_finished: false,
+        }
+    }
+}
+
+impl<S, E> Stream for SseStream<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin
+{
+    type Item = Result<SseEvent, E>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            if self.is_finished {
+                return Poll::Ready(None);
+            }
+
+            match futures::ready!(self.bytes_stream.poll_next_unpin(cx)) {
+                Some(Ok(bytes)) => {
+                    let events = self.decoder.decode(&bytes);
+                    self.events.extend(events);
+                },
+                Some(Err(error)) => {
+                    self.is_finished = true;
+                    return Poll::Ready(Some(Err(error)));
+                },
+                None => self.is_finished = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use bytes::Bytes;
+    use futures::{stream, StreamExt};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use super::{SseDecoder, SseEvent, SseStream};
+
+    const EVENT_STREAM: &str = concat!(
+        "\u{FEFF}: keep-alive\r\n",
+        "\r\n",
+        "data: {\"result\": \"你好，世界\"}\r\n",
+        "\r\n",
+        "event: update\n",
+        "id: 42\n",
+        "retry: 3000\n",
+        "data: first line\n",
+        "data:second line\n",
+        "data\n",
+        "\n",
+        "unknown: field\r",
+        "data: 🦀\r",
+        "\r",
+        "id\n",
+        "event: empty\n",
+        "\n",
+        "data: [DONE]\n",
+        "\n",
+        "data: incomplete\n",
+    );
+
+    fn expected_events() -> Vec<SseEvent> {
+        let event = |event: &str, data: &str, id: Option<&str>, retry: Option<Duration>| SseEvent {
+            event: event.to_string(),
+            data: data.to_string(),
+            id: id.map(str::to_string),
+            retry,
+        };
+
+        vec![
+            event("message", "{\"result\": \"你好，世界\"}", None, None),
+            event("update", "first line\nsecond line\n", Some("42"), Some(Duration::from_secs(3))),
+            event("message", "🦀", Some("42"), None),
+            event("message", "[DONE]", Some(""), None),
+        ]
+    }
+
+    fn decode_chunks(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut decoder = SseDecoder::new();
+        chunks
+            .iter()
+            .flat_map(|chunk| decoder.decode(chunk))
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode_chunks(&[EVENT_STREAM.as_bytes()]), expected_events());
+    }
+
+    #[test]
+    fn test_decode_split_anywhere() {
+        let bytes = EVENT_STREAM.as_bytes();
+
+        // Every split into two chunks, including within "\r\n", the BOM and Chinese characters
+        for index in 0..=bytes.len() {
+            let (first, second) = bytes.split_at(index);
+            assert_eq!(decode_chunks(&[first, second]), expected_events(), "split at {}", index);
+        }
+
+        // One byte at a time
+        let chunks = bytes.chunks(1).collect::<Vec<&[u8]>>();
+        assert_eq!(decode_chunks(&chunks), expected_events());
+    }
+
+    #[test]
+    fn test_decode_fuzzed_splits() {
+        let bytes = EVENT_STREAM.as_bytes();
+        let mut rng = StdRng::seed_from_u64(0x55E);
+
+        for _ in 0..1000 {
+            let mut chunks = vec![];
+            let mut rest = bytes;
+            while !rest.is_empty() {
+                let (chunk, remaining) = rest.split_at(rng.gen_range(0..=rest.len().min(16)));
+                chunks.push(chunk);
+                rest = remaining;
+            }
+
+            assert_eq!(decode_chunks(&chunks), expected_events(), "chunks {:?}", chunks);
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid_utf8() {
+        let events = decode_chunks(&[b"data: \xFF\n\n"]);
+        assert_eq!(events[0].data, "\u{FFFD}");
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let bytes_stream = stream::iter(vec![
+            Ok(Bytes::from_static(b"data: 1\n\ndata: 2\n")),
+            Ok(Bytes::from_static(b"\n")),
+            Err("connection reset"),
+            Ok(Bytes::from_static(b"data: 3\n\n")),
+        ]);
+
+        let items = SseStream::new(bytes_stream)
+            .map(|item| item.map(|event| event.data))
+            .collect::<Vec<_>>()
+            .await;
+
+        // The stream ends after an error
+        assert_eq!(items, vec![
+            Ok("1".to_string()),
+            Ok("2".to_string()),
+            Err("connection reset"),
+        ]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use base64::{Engine, engine::general_purpose::STANDARD};
+use fancy_regex::Regex;
+
+/// A byte-level BPE tokenizer in the format of OpenAI's tiktoken.
+/// 
+/// Special tokens such as `<|endoftext|>` are encoded as ordinary text.
+#[derive(Debug)]
+pub struct BpeTokenizer {
+    encoder: HashMap<Vec<u8>, u32>,
+    decoder: HashMap<u32, Vec<u8>>,
+
+    /// The pattern that splits the text into pieces before merging.
+    regex: Regex,
+}
+
+impl BpeTokenizer {
+    /// Create a tokenizer from a tiktoken vocabulary,
+    /// in which each line is a base64-encoded token followed by its rank.
+    pub fn from_tiktoken(vocabulary: &str, pattern: &str) -> Self {
+        let encoder = vocabulary
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let (token, rank) = line.split_once(' ').expect("invalid tiktoken vocabulary");
+                (
+                    STANDARD.decode(token).expect("invalid token in tiktoken vocabulary"),
+                    rank.parse::<u32>().expect("invalid rank in tiktoken vocabulary"),
+                )
+            })
+            .collect::<HashMap<Vec<u8>, u32>>();
+        let decoder = encoder
+            .iter()
+            .map(|(token, rank)| (*rank, token.to_owned()))
+            .collect();
+
+        Self {
+            encoder,
+            decoder,
+            regex: Regex::new(pattern).expect("invalid tokenizer pattern"),
+        }
+    }
+
+    /// Encode the text into tokens.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        let mut tokens = vec![];
+
+        for piece in self.regex.find_iter(text) {
+            let piece = piece.expect("failed to split text into pieces").as_str().as_bytes();
+
+            // Most pieces are tokens themselves
+            match self.encoder.get(piece) {
+                Some(token) => tokens.push(*token),
+                None => tokens.extend(self.byte_pair_merge(piece)),
+            }
+        }
+
+        tokens
+    }
+
+    /// Decode the tokens into text, replacing invalid UTF-8 sequences.
+    pub fn decode(&self, tokens: &[u32]) -> String {
+        let bytes = tokens
+            .iter()
+            .filter_map(|token| self.decoder.get(token))
+            .flatten()
+            .copied()
+            .collect::<Vec<u8>>();
+
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Count the tokens of the text.
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+
+    /// Merge the bytes of a piece into tokens, starting with the pair of the lowest rank.
+    fn byte_pair_merge(&self, piece: &[u8]) -> Vec<u32> {
+        // Start of each part and the rank of the part merged with the next one
+        let mut parts = (0..=piece.len())
+            .map(|start| (start, u32::MAX))
+            .collect::<Vec<(usize, u32)>>();
+        let get_rank = |parts: &[(usize, u32)], index: usize| -> u32 {
+            match parts.get(index + 2) {
+                Some((end, _)) => self.encoder
+                    .get(&piece[parts[index].0..*end])
+                    .copied()
+                    .unwrap_or(u32::MAX),
+                None => u32::MAX,
+            }
+        };
+
+        for index in 0..parts.len() {
+            parts[index].1 = get_rank(&parts, index);
+        }
+
+        loop {
+            let (index, rank) = parts
+                .iter()
+                .enumerate()
+                .map(|(index, (_, rank))| (index, *rank))
+                .min_by_key(|(_, rank)| *rank)
+                .unwrap();
+            if rank == u32::MAX {
+                break;
+            }
+
+            // Merge the part with the next one and update the affected ranks
+            parts.remove(index + 1);
+            parts[index].1 = get_rank(&parts, index);
+            if index > 0 {
+                parts[index - 1].1 = get_rank(&parts, index - 1);
+            }
+        }
+
+        parts
+            .windows(2)
+            .map(|window| self.encoder[&piece[window[0].0..window[1].0]])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BpeTokenizer;
+
+    #[test]
+    fn test_merge_by_rank() {
+        // "ab" is merged before "bc", so "abc" is split into "ab" and "c"
+        let tokenizer = BpeTokenizer::from_tiktoken(
+            "YQ== 0\nYg== 1\nYw== 2\nYWI= 3\nYmM= 4\n",
+            r"\S+|\s+",
+        );
+
+        assert_eq!(tokenizer.encode("abc"), vec![3, 2]);
+        assert_eq!(tokenizer.encode("bc"), vec![4]);
+        assert_eq!(tokenizer.decode(&[3, 2]), "abc");
+    }
+}
@@ -0,0 +1,42 @@
+use lazy_static::lazy_static;
+use super::BpeTokenizer;
+
+/// The pattern of cl100k_base that splits the text into pieces.
+const CL100K_BASE_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+lazy_static! {
+    static ref CL100K_BASE: BpeTokenizer = BpeTokenizer::from_tiktoken(
+        include_str!("cl100k_base.tiktoken"),
+        CL100K_BASE_PATTERN,
+    );
+}
+
+/// Get the tokenizer of GPT-3.5 and GPT-4, which is loaded on first use.
+pub fn cl100k_base() -> &'static BpeTokenizer {
+    &CL100K_BASE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cl100k_base;
+
+    #[test]
+    fn test_encode() {
+        let tokenizer = cl100k_base();
+
+        // Fixtures produced by tiktoken
+        let fixtures: Vec<(&str, Vec<u32>)> = vec![
+            ("hello world", vec![15339, 1917]),
+            ("tiktoken is great!", vec![83, 1609, 5963, 374, 2294, 0]),
+            ("Rust 是一门系统编程语言。", vec![49, 592, 55951, 15120, 65789, 73548, 31968, 39607, 73981, 78244, 1811]),
+            ("  indented\n\n\tcode();\r\n", vec![220, 1280, 16243, 271, 44443, 1679]),
+            ("I'm 12345 years old!!! 🦀", vec![40, 2846, 220, 4513, 1774, 1667, 2362, 12340, 11410, 99, 222]),
+            ("", vec![]),
+        ];
+
+        for (text, tokens) in fixtures {
+            assert_eq!(tokenizer.encode(text), tokens, "{:?}", text);
+            assert_eq!(tokenizer.decode(&tokens), text);
+        }
+    }
+}
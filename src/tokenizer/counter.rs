@@ -0,0 +1,23 @@
+use std::fmt::Debug;
+use async_trait::async_trait;
+use crate::{
+    Result,
+    chat::{
+        ChatModel,
+        ChatMessage,
+    },
+};
+
+/// Counts the prompt tokens of chat requests before they are sent.
+/// 
+/// OpenAI's models are counted locally with cl100k_base,
+/// and Qianfan's models are counted by its tokenizer API or estimated offline.
+#[async_trait]
+pub trait TokenCounter: Debug + Send + Sync {
+    /// Count the tokens of the prompt made of the profile of the model and the messages.
+    async fn count_prompt_tokens(
+        &self,
+        model: &ChatModel,
+        messages: &[ChatMessage],
+    ) -> Result<u32>;
+}
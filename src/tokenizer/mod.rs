@@ -0,0 +1,17 @@
+mod bpe;
+pub use bpe::BpeTokenizer;
+
+mod cl100k_base;
+pub use cl100k_base::cl100k_base;
+
+mod counter;
+pub use counter::TokenCounter;
+
+mod openai;
+pub use openai::OpenAITokenCounter;
+
+mod qianfan;
+pub use qianfan::{
+    QianfanTokenCounter,
+    QianfanTokenEstimator,
+};
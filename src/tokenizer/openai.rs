@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use crate::{
+    Result,
+    chat::{
+        ChatModel,
+        ChatMessage,
+        ChatRole,
+    },
+};
+use super::{
+    cl100k_base,
+    TokenCounter,
+};
+
+/// Tokens added to every message by the chat format of OpenAI.
+const TOKENS_PER_MESSAGE: u32 = 3;
+
+/// Tokens that prime the reply of the assistant.
+const TOKENS_PER_REPLY: u32 = 3;
+
+/// Counts the tokens of chat messages as OpenAI's GPT-3.5 and GPT-4 models do.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OpenAITokenCounter;
+
+impl OpenAITokenCounter {
+    /// Count the tokens of the text.
+    pub fn count_tokens(&self, text: &str) -> u32 {
+        cl100k_base().count_tokens(text) as u32
+    }
+
+    /// Count the prompt tokens of a chat request,
+    /// in which the profile is sent as a system message ahead of the messages.
+    /// 
+    /// Without tool calls, the count matches the `prompt_tokens` reported by OpenAI.
+    /// Tool calls are counted by their names and arguments, which is an estimate.
+    pub fn count_chat_tokens(&self, profile: Option<&str>, messages: &[ChatMessage]) -> u32 {
+        let profile_tokens = profile
+            .map(|profile| TOKENS_PER_MESSAGE + self.count_tokens("system") + self.count_tokens(profile))
+            .unwrap_or(0);
+
+        let message_tokens = messages
+            .iter()
+            .map(|message| {
+                let role = match message.role {
+                    ChatRole::User => "user",
+                    ChatRole::Assistant => "assistant",
+                    ChatRole::Tool => "tool",
+                };
+                let tool_call_tokens = message.tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|tool_call| self.count_tokens(&tool_call.name) + self.count_tokens(&tool_call.arguments))
+                    .sum::<u32>();
+
+                TOKENS_PER_MESSAGE + self.count_tokens(role) + self.count_tokens(&message.content) + tool_call_tokens
+            })
+            .sum::<u32>();
+
+        profile_tokens + message_tokens + TOKENS_PER_REPLY
+    }
+}
+
+#[async_trait]
+impl TokenCounter for OpenAITokenCounter {
+    async fn count_prompt_tokens(
+        &self,
+        model: &ChatModel,
+        messages: &[ChatMessage],
+    ) -> Result<u32> {
+        Ok(self.count_chat_tokens(model.profile.as_deref(), messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chat::{
+        ChatMessage,
+        ChatRole,
+    };
+    use super::OpenAITokenCounter;
+
+    #[test]
+    fn test_count_chat_tokens() {
+        // Fixtures produced by tiktoken for gpt-4
+        assert_eq!(
+            OpenAITokenCounter.count_chat_tokens(
+                None,
+                &[ChatMessage::new(ChatRole::User, "What is Rust?")],
+            ),
+            11
+        );
+        assert_eq!(
+            OpenAITokenCounter.count_chat_tokens(
+                Some("You are a helpful assistant."),
+                &[
+                    ChatMessage::new(ChatRole::User, "What is Rust?"),
+                    ChatMessage::new(ChatRole::Assistant, "Rust is a systems programming language."),
+                    ChatMessage::new(ChatRole::User, "用中文介绍一下它的所有权机制。"),
+                ],
+            ),
+            53
+        );
+    }
+}
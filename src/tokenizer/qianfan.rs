@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use crate::{
+    Result,
+    chat::{
+        ChatModel,
+        ChatMessage,
+    },
+    qianfan::{
+        self,
+        tokenizer::QianfanTokenizerRequestBody,
+    },
+};
+use super::TokenCounter;
+
+/// Counts the tokens of ERNIE-Bot models with Qianfan's tokenizer API.
+/// 
+/// The profile and the contents of the messages are counted as one prompt,
+/// so the count excludes the few tokens that separate the messages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QianfanTokenCounter;
+
+#[async_trait]
+impl TokenCounter for QianfanTokenCounter {
+    async fn count_prompt_tokens(
+        &self,
+        model: &ChatModel,
+        messages: &[ChatMessage],
+    ) -> Result<u32> {
+        let prompt = prompt_texts(model, messages).collect::<Vec<&str>>().join("\n");
+
+        // The API rejects empty prompts
+        if prompt.is_empty() {
+            return Ok(0);
+        }
+
+        // Call API to count tokens
+        Ok(
+            qianfan::tokenizer::count_tokens(
+                &model.client,
+                model.api_base.as_deref().unwrap_or(qianfan::QIANFAN_API_BASE),
+                &model.credential_provider.get_credentials().await?,
+                &QianfanTokenizerRequestBody::new(prompt),
+            ).await?
+            .usage
+            .prompt_tokens
+        )
+    }
+}
+
+/// Estimates the tokens of Qianfan's models offline.
+/// 
+/// It follows the rule of thumb given by Qianfan,
+/// which counts a token for each Chinese character and 1.3 tokens for each other word.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QianfanTokenEstimator;
+
+impl QianfanTokenEstimator {
+    /// Estimate the tokens of the text, rounded up.
+    pub fn estimate_tokens(&self, text: &str) -> u32 {
+        let chinese_chars = text
+            .chars()
+            .filter(|char| is_chinese_char(*char))
+            .count();
+
+        // Chinese characters separate words as spaces do
+        let words = text
+            .split(|char: char| char.is_whitespace() || is_chinese_char(char))
+            .filter(|word| !word.is_empty())
+            .count();
+
+        (chinese_chars as f64 + words as f64 * 1.3).ceil() as u32
+    }
+}
+
+#[async_trait]
+impl TokenCounter for QianfanTokenEstimator {
+    async fn count_prompt_tokens(
+        &self,
+        model: &ChatModel,
+        messages: &[ChatMessage],
+    ) -> Result<u32> {
+        Ok(
+            prompt_texts(model, messages)
+                .map(|text| self.estimate_tokens(text))
+                .sum()
+        )
+    }
+}
+
+/// Get the texts that make up the prompt, i.e. the profile, the contents and the tool calls.
+fn prompt_texts<'a>(model: &'a ChatModel, messages: &'a [ChatMessage]) -> impl Iterator<Item = &'a str> {
+    model.profile
+        .as_deref()
+        .into_iter()
+        .chain(
+            messages
+                .iter()
+                .flat_map(|message| {
+                    std::iter::once(message.content.as_str())
+                        .chain(
+                            message.tool_calls
+                                .iter()
+                                .flatten()
+                                .flat_map(|tool_call| [tool_call.name.as_str(), tool_call.arguments.as_str()])
+                        )
+                })
+        )
+        .filter(|text| !text.is_empty())
+}
+
+/// Check if the character is a CJK unified ideograph.
+fn is_chinese_char(char: char) -> bool {
+    matches!(char, '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}' | '\u{20000}'..='\u{2a6df}')
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use wiremock::{
+        Mock,
+        MockServer,
+        ResponseTemplate,
+        matchers::path,
+    };
+    use crate::{
+        Credentials,
+        StaticCredentialProvider,
+        chat::{
+            ChatModel,
+            ChatModelName,
+            ChatMessage,
+            ChatRole,
+        },
+        qianfan::mock_access_token,
+        tokenizer::TokenCounter,
+    };
+    use super::{
+        QianfanTokenCounter,
+        QianfanTokenEstimator,
+    };
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(QianfanTokenEstimator.estimate_tokens(""), 0);
+        assert_eq!(QianfanTokenEstimator.estimate_tokens("你好，世界"), 4 + 2);
+        assert_eq!(QianfanTokenEstimator.estimate_tokens("What is Rust?"), 4);
+        assert_eq!(QianfanTokenEstimator.estimate_tokens("Rust 是一门系统编程语言"), 9 + 2);
+    }
+
+    #[tokio::test]
+    async fn test_count_prompt_tokens() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_access_token(&server, "token-token-counter").await;
+        Mock::given(path("/rpc/2.0/ai_custom/v1/wenxinworkshop/tokenizer/erniebot"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                r#"{"object":"tokenizer","created":1700000000,"usage":{"prompt_tokens":12,"total_tokens":12}}"#,
+                "application/json",
+            ))
+            .mount(&server)
+            .await;
+        let model = ChatModel::builder()
+            .name(ChatModelName::QianfanErnieBot)
+            .profile("你是一个助手。")
+            .credential_provider(StaticCredentialProvider::new(
+                Credentials::builder()
+                    .qianfan_access_key("ak-token-counter")
+                    .qianfan_secret_key("sk-token-counter")
+                    .build()
+            ))
+            .api_base(server.uri())
+            .build();
+        let messages = [
+            ChatMessage::new(ChatRole::User, "What is Rust?"),
+            ChatMessage::new(ChatRole::Assistant, "一门系统编程语言"),
+        ];
+
+        assert_eq!(QianfanTokenCounter.count_prompt_tokens(&model, &messages).await?, 12);
+        assert_eq!(
+            server.received_requests().await.unwrap()[1].body_json::<serde_json::Value>()?["prompt"],
+            "你是一个助手。\nWhat is Rust?\n一门系统编程语言"
+        );
+
+        // 6 Chinese characters and 1 word, 3 words, 8 Chinese characters
+        assert_eq!(QianfanTokenEstimator.count_prompt_tokens(&model, &messages).await?, 8 + 4 + 8);
+
+        Ok(())
+    }
+}